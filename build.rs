@@ -28,4 +28,8 @@ fn generate_windows_resource() {
         .set("OriginalFilename", "AutoLoginGUET.exe");
     
     res.compile().unwrap();
-}
\ No newline at end of file
+}
+
+/// 非Windows主机上无法嵌入Windows资源，交叉编译时跳过
+#[cfg(not(windows))]
+fn generate_windows_resource() {}
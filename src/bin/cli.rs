@@ -0,0 +1,7 @@
+//! AutoLoginGUET 命令行入口
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    autologinguet_core::cli::run()
+}
@@ -0,0 +1,548 @@
+//! 命令行子命令实现
+
+use crate::cli::{
+    ConfigAction, DevicesAction, EXIT_FAILURE, EXIT_NOT_LOGGED_IN, EXIT_SUCCESS, ProfileAction,
+};
+use crate::core::config::{
+    AccountConfig, AccountProfile, ConfigData, is_config_complete, normalize_isp, save_config,
+};
+use crate::core::crypto::{encrypt_password_with_machine_key, encrypt_password_with_passphrase};
+use crate::core::error::{AppError, AppResult};
+use crate::core::events::{AppEvent, EventHandler};
+use crate::core::flow::FlowService;
+use crate::core::flow_history::{FLOW_HISTORY_FILE_NAME, FlowHistory};
+use crate::core::message::CampusNetworkStatus;
+use crate::core::network::NetworkManager;
+use crate::core::passphrase::{PASSPHRASE_ENV, session_passphrase, set_session_passphrase};
+use crate::core::paths::{config_location, state_file_path};
+use crate::core::secret_store::{secret_store_for_new_account, secret_store_from_config};
+use crate::core::secret_string::SecretString;
+use crate::core::service::AuthService;
+use crate::core::watch::NetworkWatcher;
+use chrono::Local;
+use std::fs::{self, File};
+use std::io::{IsTerminal, Read, Seek, SeekFrom};
+use std::time::{Duration, Instant};
+use toml::Value;
+
+/// 日志跟踪模式下的轮询间隔
+const LOG_FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 查询校园网和广域网状态
+pub async fn status(config: ConfigData, verbose: bool) -> AppResult<u8> {
+    let network_manager = NetworkManager::new(config.network);
+
+    let campus_status = network_manager.check_campus_network().await?;
+    let wan_result = network_manager.check_wan_network_detailed().await;
+
+    println!("{}", campus_status.to_message());
+    match wan_result.successful_probe() {
+        Some(probe) => println!("{}（{}）", wan_result.status.to_message(), probe.target),
+        None => println!("{}", wan_result.status.to_message()),
+    }
+
+    if verbose {
+        for probe in &wan_result.probes {
+            println!(
+                "  {} {} 用时{:.2}秒",
+                probe.target,
+                probe.outcome.to_message(),
+                probe.elapsed_time
+            );
+        }
+        println!("  总用时{:.2}秒", wan_result.elapsed_time);
+    }
+
+    if campus_status == CampusNetworkStatus::AlreadyLoggedIn {
+        Ok(EXIT_SUCCESS)
+    } else {
+        Ok(EXIT_NOT_LOGGED_IN)
+    }
+}
+
+/// 登录校园网
+///
+/// 与静默模式使用相同的流程，已登录时不会重复发送登录请求
+pub async fn login(config: ConfigData) -> AppResult<u8> {
+    let auth_service = AuthService::new_with_startup_time(config.clone(), Some(Instant::now()));
+    let result = auth_service.silent_login(config).await?;
+
+    if result.success {
+        println!("{}", result.message);
+        Ok(EXIT_SUCCESS)
+    } else {
+        eprintln!("{}", result.message);
+        Ok(EXIT_FAILURE)
+    }
+}
+
+/// 注销校园网
+pub async fn logout(config: ConfigData) -> AppResult<u8> {
+    let auth_service = AuthService::new(config);
+    let result = auth_service.logout().await?;
+
+    if result.success {
+        println!("{}", result.message);
+        Ok(EXIT_SUCCESS)
+    } else {
+        eprintln!("{}", result.message);
+        Ok(EXIT_FAILURE)
+    }
+}
+
+/// 查询剩余流量、余额和套餐
+pub async fn flow(config: ConfigData) -> AppResult<u8> {
+    if !is_config_complete(&config) {
+        return Err(AppError::ConfigError("配置不完整".to_string()));
+    }
+
+    if !config.account.isp.is_empty() {
+        return Err(AppError::ConfigError(
+            "流量查询仅适用于校园网运营商".to_string(),
+        ));
+    }
+
+    let password = secret_store_from_config(&config.secret).get_password(&config.account)?;
+
+    let flow_service = FlowService::new()
+        .with_base_url(&config.network.self_service_url)
+        .with_history(FlowHistory::new(state_file_path(FLOW_HISTORY_FILE_NAME)));
+
+    match flow_service
+        .get_account_info(&config.account.username, &password)
+        .await
+    {
+        Ok(account_info) => {
+            println!("{}", account_info.flow_info().format_flow_info());
+            for line in [account_info.format_balance(), account_info.format_package()] {
+                if !line.is_empty() {
+                    println!("{}", line);
+                }
+            }
+            if let Some(forecast) = flow_service.flow_forecast(&config.account.username) {
+                println!("{}", forecast.to_message());
+            }
+            Ok(EXIT_SUCCESS)
+        }
+        Err(e) => {
+            eprintln!("获取流量信息失败: {}", e);
+            Ok(EXIT_FAILURE)
+        }
+    }
+}
+
+/// 查看在线设备或强制设备下线
+pub async fn devices(config: ConfigData, action: DevicesAction) -> AppResult<u8> {
+    let auth_service = AuthService::new(config);
+    let devices = auth_service.list_online_devices().await?;
+
+    match action {
+        DevicesAction::List => {
+            if devices.is_empty() {
+                println!("没有在线设备");
+            }
+            for device in &devices {
+                println!("{} {}", device.session_id, device.to_message());
+            }
+        }
+        DevicesAction::Kick { target } => {
+            let Some(device) = devices.iter().find(|device| device.matches(&target)) else {
+                eprintln!("未找到在线设备: {}", target);
+                return Ok(EXIT_FAILURE);
+            };
+
+            auth_service.kick_device(&device.session_id).await?;
+            println!("已强制下线: {}", device.to_message());
+        }
+    }
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// 守护模式：持续检测网络状态，掉线后自动重新登录
+pub async fn watch(config: ConfigData) -> AppResult<u8> {
+    let mut auth_service = AuthService::new(config.clone());
+    auth_service.set_event_handler(Box::new(ConsoleEventHandler));
+
+    println!(
+        "守护模式已启动，检测间隔{}秒，按Ctrl+C退出",
+        config.watch.interval_secs
+    );
+    NetworkWatcher::new(auth_service, config).run().await;
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// 将状态变化输出到终端的事件处理器
+struct ConsoleEventHandler;
+
+impl EventHandler for ConsoleEventHandler {
+    fn handle_event(&self, event: AppEvent) {
+        if let AppEvent::NetworkStatusChecked { message, .. } = event {
+            println!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+        }
+    }
+}
+
+/// 查看或修改配置
+pub fn config(config: ConfigData, action: ConfigAction) -> AppResult<u8> {
+    match action {
+        ConfigAction::Get { key } => config_get(&config, key.as_deref()),
+        ConfigAction::Set { key, value } => config_set(config, &key, value.as_deref()),
+        ConfigAction::Path => config_path(&config),
+    }
+}
+
+/// 输出配置文件和日志文件的位置
+fn config_path(config: &ConfigData) -> AppResult<u8> {
+    let location = config_location();
+    println!(
+        "配置文件: {}（{}）",
+        location.path.display(),
+        location.source.to_message()
+    );
+    println!(
+        "日志文件: {}",
+        config.logging.resolved_log_file_path().display()
+    );
+    Ok(EXIT_SUCCESS)
+}
+
+/// 读取配置项
+fn config_get(config: &ConfigData, key: Option<&str>) -> AppResult<u8> {
+    let root = Value::try_from(config)
+        .map_err(|e| AppError::ConfigError(format!("序列化配置失败: {}", e)))?;
+
+    let Some(key) = key else {
+        let content = toml::to_string_pretty(&root)
+            .map_err(|e| AppError::ConfigError(format!("序列化配置失败: {}", e)))?;
+        print!("{}", content);
+        return Ok(EXIT_SUCCESS);
+    };
+
+    match lookup(&root, key) {
+        Some(Value::String(s)) => println!("{}", s),
+        Some(table @ Value::Table(_)) => {
+            let content = toml::to_string_pretty(table)
+                .map_err(|e| AppError::ConfigError(format!("序列化配置失败: {}", e)))?;
+            print!("{}", content);
+        }
+        Some(value) => println!("{}", value),
+        None => return Err(AppError::ConfigError(format!("未知的配置项: {}", key))),
+    }
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// 修改配置项并保存
+fn config_set(mut config: ConfigData, key: &str, value: Option<&str>) -> AppResult<u8> {
+    if key == "account.password" {
+        // 命令行参数会出现在进程列表和shell历史中
+        if value.is_some() {
+            return Err(AppError::ConfigError(
+                "不支持在命令行中传入密码，请省略密码后在终端中输入，或通过标准输入传入"
+                    .to_string(),
+            ));
+        }
+        let password = read_account_password()?;
+        secret_store_from_config(&config.secret)
+            .set_password(&mut config.account, password.expose())?;
+    } else {
+        let value =
+            value.ok_or_else(|| AppError::ConfigError(format!("缺少配置项 {} 的值", key)))?;
+        config = set_value(&config, key, value)?;
+    }
+
+    save_config(&config)?;
+
+    // 开机自启需要同步到系统的自启动项，否则下次加载配置时会被还原
+    if key == "settings.auto_start" {
+        AuthService::new(config.clone()).set_auto_start(config.settings.auto_start)?;
+    }
+
+    println!("已更新 {}", key);
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// 按原配置项的类型修改`section.field`形式的配置项，返回修改后的配置
+fn set_value(config: &ConfigData, key: &str, value: &str) -> AppResult<ConfigData> {
+    let mut root = Value::try_from(config)
+        .map_err(|e| AppError::ConfigError(format!("序列化配置失败: {}", e)))?;
+
+    let slot = lookup_mut(&mut root, key)
+        .ok_or_else(|| AppError::ConfigError(format!("未知的配置项: {}", key)))?;
+    *slot = parse_value_like(slot, value)
+        .ok_or_else(|| AppError::ConfigError(format!("配置项 {} 的值无效: {}", key, value)))?;
+
+    let mut config: ConfigData = root
+        .try_into()
+        .map_err(|e| AppError::ConfigError(format!("配置格式错误: {}", e)))?;
+    config.account.isp = normalize_isp(&config.account.isp);
+    Ok(config)
+}
+
+/// 按`section.field`形式查找配置项
+fn lookup<'a>(root: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.').try_fold(root, |value, part| value.get(part))
+}
+
+/// 按`section.field`形式查找可修改的配置项，只允许修改已存在的标量值
+fn lookup_mut<'a>(root: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    let slot = key
+        .split('.')
+        .try_fold(root, |value, part| value.get_mut(part))?;
+
+    match slot {
+        Value::Table(_) | Value::Array(_) => None,
+        _ => Some(slot),
+    }
+}
+
+/// 按照原配置项的类型解析新值
+fn parse_value_like(current: &Value, input: &str) -> Option<Value> {
+    match current {
+        Value::String(_) => Some(Value::String(input.to_string())),
+        Value::Boolean(_) => input.parse().ok().map(Value::Boolean),
+        Value::Integer(_) => input.parse().ok().map(Value::Integer),
+        Value::Float(_) => input.parse().ok().map(Value::Float),
+        _ => None,
+    }
+}
+
+/// 查看、添加或切换账号档案
+pub fn profile(mut config: ConfigData, action: ProfileAction) -> AppResult<u8> {
+    match action {
+        ProfileAction::List => {
+            if config.profiles.accounts.is_empty() {
+                println!("未配置账号档案");
+                return Ok(EXIT_SUCCESS);
+            }
+
+            for profile in &config.profiles.accounts {
+                let marker = if profile.name == config.profiles.default {
+                    "*"
+                } else {
+                    " "
+                };
+                let isp = if profile.isp.is_empty() {
+                    "校园网"
+                } else {
+                    profile.isp.as_str()
+                };
+                println!(
+                    "{} {} {}（{}）",
+                    marker, profile.name, profile.username, isp
+                );
+            }
+            if !config.profiles.fallback.is_empty() {
+                println!("备用顺序: {}", config.profiles.fallback.join(" -> "));
+            }
+        }
+        ProfileAction::Use { name } => {
+            config.switch_profile(&name)?;
+            save_config(&config)?;
+            println!("已切换到账号档案: {}", name);
+        }
+        ProfileAction::Add {
+            name,
+            username,
+            isp,
+        } => {
+            let password = read_account_password()?;
+
+            // 新密码与配置中已保存的密码使用相同的加密方式
+            let mut account = AccountConfig {
+                username,
+                encrypted_password: String::new(),
+                isp: normalize_isp(&isp),
+            };
+            secret_store_for_new_account(&config).set_password(&mut account, password.expose())?;
+
+            config.set_profile(AccountProfile {
+                name: name.clone(),
+                username: account.username,
+                encrypted_password: account.encrypted_password,
+                isp: account.isp,
+            });
+            save_config(&config)?;
+            println!("已保存账号档案: {}", name);
+        }
+    }
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// 改用主密码加密已保存的密码
+///
+/// 已通过环境变量或文件描述符提供主密码时直接使用，否则在终端中输入两次
+pub fn export(mut config: ConfigData) -> AppResult<u8> {
+    let passphrase = match session_passphrase() {
+        Some(passphrase) => passphrase,
+        None => read_new_passphrase()?,
+    };
+
+    let count = config.rewrap_passwords(|password| {
+        encrypt_password_with_passphrase(password, passphrase.expose())
+    })?;
+    save_config(&config)?;
+
+    println!(
+        "已使用主密码重新加密{}个密码，配置文件可以复制到其他电脑使用，运行时需要提供主密码",
+        count
+    );
+    Ok(EXIT_SUCCESS)
+}
+
+/// 改用本机密钥加密已保存的密码
+pub fn import(mut config: ConfigData) -> AppResult<u8> {
+    let count = config.rewrap_passwords(encrypt_password_with_machine_key)?;
+    save_config(&config)?;
+
+    println!("已使用本机密钥重新加密{}个密码", count);
+    Ok(EXIT_SUCCESS)
+}
+
+/// 在终端中输入主密码，作为本次运行的主密码
+///
+/// 标准输入不是终端时（如开机自启或后台服务）不做任何操作，解密时再提示缺少主密码
+pub fn prompt_passphrase() -> AppResult<()> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+
+    let passphrase = rpassword::prompt_password("请输入主密码: ")
+        .map_err(|e| AppError::SystemError(format!("读取主密码失败: {}", e)))?;
+    set_session_passphrase(SecretString::new(passphrase));
+    Ok(())
+}
+
+/// 读取要保存的账号密码
+///
+/// 在终端中运行时输入两次，否则从标准输入读取第一行，如`pass show guet | autologinguet-cli config set account.password`
+pub fn read_account_password() -> AppResult<SecretString> {
+    let password = if std::io::stdin().is_terminal() {
+        let read = |prompt: &str| {
+            rpassword::prompt_password(prompt)
+                .map(SecretString::new)
+                .map_err(|e| AppError::SystemError(format!("读取密码失败: {}", e)))
+        };
+
+        let password = read("请输入校园网密码: ")?;
+        if read("请再次输入校园网密码: ")? != password {
+            return Err(AppError::ConfigError("两次输入的密码不一致".to_string()));
+        }
+        password
+    } else {
+        let mut line = zeroize::Zeroizing::new(String::new());
+        std::io::stdin()
+            .read_line(&mut line)
+            .map_err(|e| AppError::SystemError(format!("从标准输入读取密码失败: {}", e)))?;
+        SecretString::from(line.trim_end_matches(['\r', '\n']))
+    };
+
+    if password.is_empty() {
+        return Err(AppError::ConfigError("密码不能为空".to_string()));
+    }
+    Ok(password)
+}
+
+/// 在终端中设置新的主密码，需要输入两次
+fn read_new_passphrase() -> AppResult<SecretString> {
+    if !std::io::stdin().is_terminal() {
+        return Err(AppError::ConfigError(format!(
+            "未提供主密码，请在终端中运行或设置环境变量{}",
+            PASSPHRASE_ENV
+        )));
+    }
+
+    let read = |prompt: &str| {
+        rpassword::prompt_password(prompt)
+            .map(SecretString::new)
+            .map_err(|e| AppError::SystemError(format!("读取主密码失败: {}", e)))
+    };
+
+    let passphrase = read("请设置主密码: ")?;
+    if passphrase.is_empty() {
+        return Err(AppError::ConfigError("主密码不能为空".to_string()));
+    }
+    if read("请再次输入主密码: ")? != passphrase {
+        return Err(AppError::ConfigError("两次输入的主密码不一致".to_string()));
+    }
+
+    Ok(passphrase)
+}
+
+/// 输出日志末尾若干行，`follow`为真时持续输出新写入的内容
+pub fn logs_tail(config: &ConfigData, lines: usize, follow: bool) -> AppResult<u8> {
+    let log_file_path = config.logging.resolved_log_file_path();
+
+    if !log_file_path.exists() {
+        eprintln!("日志文件不存在: {}", log_file_path.display());
+        return Ok(EXIT_FAILURE);
+    }
+
+    let content = fs::read_to_string(&log_file_path).map_err(|e| {
+        AppError::LogError(format!(
+            "无法读取日志文件 '{}': {}",
+            log_file_path.display(),
+            e
+        ))
+    })?;
+
+    let all_lines: Vec<&str> = content.lines().collect();
+    for line in &all_lines[all_lines.len().saturating_sub(lines)..] {
+        println!("{}", line);
+    }
+
+    if !follow {
+        return Ok(EXIT_SUCCESS);
+    }
+
+    let mut position = content.len() as u64;
+    loop {
+        std::thread::sleep(LOG_FOLLOW_INTERVAL);
+
+        let Ok(metadata) = fs::metadata(&log_file_path) else {
+            continue;
+        };
+
+        // 日志清理会重写文件，文件变短时从头开始读取
+        if metadata.len() < position {
+            position = 0;
+        }
+
+        if metadata.len() > position {
+            let mut file = File::open(&log_file_path)
+                .map_err(|e| AppError::LogError(format!("无法打开日志文件: {}", e)))?;
+            file.seek(SeekFrom::Start(position))
+                .map_err(|e| AppError::LogError(format!("读取日志文件失败: {}", e)))?;
+
+            let mut appended = String::new();
+            file.read_to_string(&mut appended)
+                .map_err(|e| AppError::LogError(format!("读取日志文件失败: {}", e)))?;
+
+            print!("{}", appended);
+            position += appended.len() as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_value_keeps_backslashes() {
+        let path = r"C:\Users\nobody\x.log";
+        let config = set_value(&ConfigData::default(), "logging.log_file_path", path).unwrap();
+        assert_eq!(config.logging.log_file_path, path);
+
+        let content = toml::to_string_pretty(&config).unwrap();
+        let parsed: ConfigData = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.logging.log_file_path, path);
+
+        assert!(set_value(&config, "settings.auto_start", "maybe").is_err());
+        assert!(set_value(&config, "logging.missing", "1").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! 命令行模块
+//!
+//! 提供不依赖GUI的命令行接口，适用于没有显示器的实验室主机和家庭服务器
+
+mod commands;
+
+use crate::core::config::load_config_with_report;
+use crate::core::error::{AppError, AppResult};
+use crate::core::passphrase::{read_passphrase_fd, session_passphrase, set_session_passphrase};
+use crate::core::paths::{config_path_from_args, set_config_path};
+use clap::{Parser, Subcommand};
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// 退出码：操作成功
+pub const EXIT_SUCCESS: u8 = 0;
+/// 退出码：操作失败
+pub const EXIT_FAILURE: u8 = 1;
+/// 退出码：命令行参数错误（与clap保持一致）
+pub const EXIT_USAGE: u8 = 2;
+/// 退出码：当前未登录校园网
+pub const EXIT_NOT_LOGGED_IN: u8 = 3;
+/// 退出码：配置缺失或配置错误
+pub const EXIT_CONFIG_ERROR: u8 = 4;
+
+/// 命令行参数
+#[derive(Debug, Parser)]
+#[command(
+    name = "autologinguet-cli",
+    version,
+    about = "GUET校园网自动登录命令行工具"
+)]
+pub struct Cli {
+    /// 配置文件路径，优先于环境变量`AUTOLOGINGUET_CONFIG`和默认位置
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// 从文件描述符读取主密码，供守护进程使用；也可以通过环境变量`AUTOLOGINGUET_PASSPHRASE`提供
+    #[arg(long, global = true, value_name = "FD")]
+    pub passphrase_fd: Option<u32>,
+    /// 子命令
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// 子命令
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 查询校园网和广域网状态，未登录时返回退出码3
+    Status {
+        /// 输出每个广域网探测目标的结果和耗时
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// 登录校园网，已登录时直接返回
+    Login,
+    /// 注销校园网
+    Logout,
+    /// 查询剩余流量、余额、套餐和消耗预测（仅校园网运营商）
+    Flow,
+    /// 查看在线设备或强制设备下线
+    Devices {
+        #[command(subcommand)]
+        action: DevicesAction,
+    },
+    /// 守护模式：持续检测网络状态，掉线后自动重新登录
+    Watch,
+    /// 查看或修改配置
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// 查看日志
+    Logs {
+        #[command(subcommand)]
+        action: LogsAction,
+    },
+    /// 查看、添加或切换账号档案
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// 改用主密码加密已保存的密码，配置文件可以复制到其他电脑使用
+    Export,
+    /// 改用本机密钥加密已保存的密码，之后不再需要主密码
+    Import,
+}
+
+impl Command {
+    /// 是否需要解密或加密已保存的密码
+    fn uses_password(&self) -> bool {
+        match self {
+            Command::Login
+            | Command::Flow
+            | Command::Devices { .. }
+            | Command::Watch
+            | Command::Export
+            | Command::Import
+            | Command::Profile {
+                action: ProfileAction::Add { .. },
+            } => true,
+            Command::Config {
+                action: ConfigAction::Set { key, .. },
+            } => key == "account.password",
+            _ => false,
+        }
+    }
+}
+
+/// 配置子命令
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// 读取配置项，如`account.username`；省略时输出完整配置
+    Get { key: Option<String> },
+    /// 修改配置项；`account.password`不在命令行中传入，在终端中输入或从标准输入读取第一行
+    Set { key: String, value: Option<String> },
+    /// 输出配置文件和日志文件的位置
+    Path,
+}
+
+/// 账号档案子命令
+#[derive(Debug, Subcommand)]
+pub enum ProfileAction {
+    /// 列出账号档案，`*`标记默认档案
+    List,
+    /// 切换默认档案
+    Use { name: String },
+    /// 添加账号档案，同名档案会被替换；密码在终端中输入或从标准输入读取第一行，会自动加密保存
+    Add {
+        name: String,
+        username: String,
+        /// 运营商，如`cmcc`，省略时为校园网
+        #[arg(long, default_value = "")]
+        isp: String,
+    },
+}
+
+/// 在线设备子命令
+#[derive(Debug, Subcommand)]
+pub enum DevicesAction {
+    /// 列出当前账号的在线设备
+    List,
+    /// 强制设备下线，可指定会话编号、IP或MAC地址
+    Kick { target: String },
+}
+
+/// 日志子命令
+#[derive(Debug, Subcommand)]
+pub enum LogsAction {
+    /// 输出日志末尾若干行
+    Tail {
+        /// 输出的行数
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+        /// 持续输出新写入的日志
+        #[arg(short, long)]
+        follow: bool,
+    },
+}
+
+/// 命令行入口
+///
+/// 解析命令行参数并执行对应子命令，返回进程退出码
+pub fn run() -> ExitCode {
+    // 兼容开机自启写入的"-silent"参数，等同于`login`
+    let args: Vec<String> = env::args().collect();
+    let cli = if args.get(1).map(String::as_str) == Some("-silent") {
+        Cli {
+            config: config_path_from_args(&args),
+            passphrase_fd: None,
+            command: Command::Login,
+        }
+    } else {
+        Cli::parse()
+    };
+
+    if let Some(path) = cli.config {
+        set_config_path(path);
+    }
+
+    if let Some(fd) = cli.passphrase_fd {
+        match read_passphrase_fd(fd) {
+            Ok(passphrase) => set_session_passphrase(passphrase),
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::from(EXIT_CONFIG_ERROR);
+            }
+        }
+    }
+
+    ExitCode::from(run_command(cli.command))
+}
+
+/// 执行子命令
+pub fn run_command(command: Command) -> u8 {
+    let config = match load_config_with_report() {
+        Ok((config, report)) => {
+            if report.has_changes() {
+                eprintln!("{}", report.to_message());
+            }
+            config
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_CONFIG_ERROR;
+        }
+    };
+
+    // 密码使用主密码加密时，在终端中提示输入一次，本次运行中不再重复询问
+    if command.uses_password()
+        && config.uses_passphrase()
+        && session_passphrase().is_none()
+        && let Err(e) = commands::prompt_passphrase()
+    {
+        eprintln!("{}", e);
+        return EXIT_CONFIG_ERROR;
+    }
+
+    let result = match command {
+        Command::Status { verbose } => block_on(commands::status(config, verbose)),
+        Command::Login => block_on(commands::login(config)),
+        Command::Logout => block_on(commands::logout(config)),
+        Command::Flow => block_on(commands::flow(config)),
+        Command::Devices { action } => block_on(commands::devices(config, action)),
+        Command::Watch => block_on(commands::watch(config)),
+        Command::Config { action } => commands::config(config, action),
+        Command::Logs {
+            action: LogsAction::Tail { lines, follow },
+        } => commands::logs_tail(&config, lines, follow),
+        Command::Profile { action } => commands::profile(config, action),
+        Command::Export => commands::export(config),
+        Command::Import => commands::import(config),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            match e {
+                AppError::ConfigError(_) | AppError::PasswordDecryptionError { .. } => {
+                    EXIT_CONFIG_ERROR
+                }
+                _ => EXIT_FAILURE,
+            }
+        }
+    }
+}
+
+/// 在单线程Tokio Runtime中执行异步子命令
+fn block_on<F>(future: F) -> AppResult<u8>
+where
+    F: Future<Output = AppResult<u8>>,
+{
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| AppError::SystemError(format!("创建Tokio Runtime失败: {}", e)))?;
+
+    rt.block_on(future)
+}
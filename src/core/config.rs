@@ -4,22 +4,30 @@
 
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
 use toml;
+use toml_edit::{DocumentMut, Item, Table, Value};
 
 use crate::core::error::{AppError, AppResult};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex as TokioMutex;
 
-use crate::core::crypto::generate_encrypted_password;
-use crate::core::dto::GuiConfigDto;
+use crate::core::crypto::{
+    CiphertextFormat, ciphertext_format, decrypt_config_password, upgrade_machine_key_ciphertext,
+};
+use crate::core::flow_alert::FlowAlertConfig;
+use crate::core::flow_switch::FlowSwitchConfig;
+use crate::core::migration::{self, CURRENT_CONFIG_VERSION, MigrationReport};
 use crate::core::network::NetworkConfig;
-
-const DEFAULT_LOG_FILE_PATH: &str = "./AutoLogin.log";
-const CONFIG_FILE_NAME: &str = "config.toml";
+use crate::core::paths::{self, CONFIG_FILE_NAME};
+use crate::core::secret_store::SecretConfig;
+use crate::core::template::{MessageTemplate, TemplateNeeds};
 
 /// 账户配置信息
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
@@ -32,24 +40,51 @@ pub struct AccountConfig {
     pub isp: String,
 }
 
-impl From<GuiConfigDto> for AccountConfig {
-    fn from(gui_config: GuiConfigDto) -> Self {
-        let encrypted_password = if !gui_config.password.is_empty() {
-            // 只有在提供了新密码时才进行加密
-            generate_encrypted_password(&gui_config.password)
-        } else {
-            // 否则使用已有的加密密码
-            gui_config.encrypted_password.clone()
-        };
+/// 账号档案
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct AccountProfile {
+    /// 档案名称
+    pub name: String,
+    /// 用户名
+    pub username: String,
+    /// 加密后的密码
+    pub encrypted_password: String,
+    /// ISP运营商
+    pub isp: String,
+}
 
+impl AccountProfile {
+    /// 转换为账户配置
+    pub fn to_account(&self) -> AccountConfig {
         AccountConfig {
-            username: gui_config.username.clone(),
-            encrypted_password,
-            isp: normalize_isp(&gui_config.isp),
+            username: self.username.clone(),
+            encrypted_password: self.encrypted_password.clone(),
+            isp: self.isp.clone(),
         }
     }
 }
 
+/// 多账号配置
+///
+/// `[account]`保存默认档案的账号，切换档案时同步更新
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ProfilesConfig {
+    /// 默认档案名称，为空时只使用`[account]`中的账号
+    pub default: String,
+    /// 默认账号登录失败后依次尝试的档案名称
+    pub fallback: Vec<String>,
+    /// 账号档案
+    pub accounts: Vec<AccountProfile>,
+}
+
+impl ProfilesConfig {
+    /// 按名称查找档案
+    pub fn get(&self, name: &str) -> Option<&AccountProfile> {
+        self.accounts.iter().find(|profile| profile.name == name)
+    }
+}
+
 /// 标准化ISP值的统一函数
 pub fn normalize_isp(isp: &str) -> String {
     if isp == "校园网" {
@@ -59,12 +94,23 @@ pub fn normalize_isp(isp: &str) -> String {
     }
 }
 
+/// 运营商的显示名称，用于消息模板中的`{isp}`
+pub fn isp_display_name(isp: &str) -> String {
+    match isp.trim_start_matches('@') {
+        "" => "校园网".to_string(),
+        "cmcc" => "中国移动".to_string(),
+        "unicom" => "中国联通".to_string(),
+        "telecom" => "中国电信".to_string(),
+        other => other.to_string(),
+    }
+}
+
 /// 日志配置信息
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct LoggingConfig {
     /// 是否启用日志记录
     pub enable_logging: bool,
-    /// 日志文件路径
+    /// 日志文件路径，相对路径以配置文件所在目录为基准
     pub log_file_path: String,
     /// INFO等级的日志保留天数
     pub info_log_retention_days: i64,
@@ -74,12 +120,19 @@ impl Default for LoggingConfig {
     fn default() -> Self {
         LoggingConfig {
             enable_logging: true,
-            log_file_path: DEFAULT_LOG_FILE_PATH.to_string(),
+            log_file_path: paths::default_log_file_path(),
             info_log_retention_days: 7,
         }
     }
 }
 
+impl LoggingConfig {
+    /// 解析后的日志文件路径
+    pub fn resolved_log_file_path(&self) -> PathBuf {
+        paths::resolve_log_file_path(&self.log_file_path)
+    }
+}
+
 /// 消息配置信息
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct MessageConfig {
@@ -93,27 +146,36 @@ pub struct MessageConfig {
 
 impl Default for MessageConfig {
     fn default() -> Self {
-        // 默认使用校园网运营商的配置（包含流量信息%4）
+        // 默认使用校园网运营商的配置（包含剩余流量）
         MessageConfig::for_campus_network()
     }
 }
 
 impl MessageConfig {
-    /// 为校园网运营商创建默认消息配置（包含流量信息%4）
+    /// 为校园网运营商创建默认消息配置（包含剩余流量）
     pub fn for_campus_network() -> Self {
         MessageConfig {
-            notify_text: "%1 %2\n%3 %4".to_string(),
-            gui_text: "%1 %2".to_string(),
-            log_text: "%1 %2 %3 %4".to_string(),
+            notify_text: "{campus} {wan}\n{elapsed} {flow}".to_string(),
+            gui_text: "{campus} {wan}".to_string(),
+            log_text: "{campus} {wan} {elapsed} {flow}".to_string(),
         }
     }
 
-    /// 为非校园网运营商创建默认消息配置（不包含流量信息%4）
+    /// 三个模板需要的数据
+    pub fn needs(&self) -> TemplateNeeds {
+        [&self.notify_text, &self.gui_text, &self.log_text]
+            .iter()
+            .fold(TemplateNeeds::default(), |needs, template| {
+                needs.merge(MessageTemplate::parse(template).needs())
+            })
+    }
+
+    /// 为非校园网运营商创建默认消息配置（不包含剩余流量）
     pub fn for_non_campus_network() -> Self {
         MessageConfig {
-            notify_text: "%1 %2 %3".to_string(),
-            gui_text: "%1 %2".to_string(),
-            log_text: "%1 %2 %3".to_string(),
+            notify_text: "{campus} {wan} {elapsed}".to_string(),
+            gui_text: "{campus} {wan}".to_string(),
+            log_text: "{campus} {wan} {elapsed}".to_string(),
         }
     }
 }
@@ -126,9 +188,34 @@ pub struct SettingsConfig {
     pub auto_start: bool,
 }
 
+/// 守护模式配置信息
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// 网络状态检测间隔（秒）
+    pub interval_secs: u64,
+    /// 连续登录失败后的最大退避间隔（秒）
+    pub max_backoff_secs: u64,
+    /// 是否同时检测广域网状态（仅用于记录状态变化）
+    pub check_wan: bool,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            interval_secs: 60,
+            max_backoff_secs: 1800,
+            check_wan: true,
+        }
+    }
+}
+
 /// 完整配置数据结构
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ConfigData {
+    /// 配置结构版本，缺失时视为版本0
+    #[serde(default)]
+    pub config_version: u32,
     /// 账户配置
     #[serde(default)]
     pub account: AccountConfig,
@@ -144,6 +231,39 @@ pub struct ConfigData {
     /// 消息配置
     #[serde(default)]
     pub message: MessageConfig,
+    /// 守护模式配置
+    #[serde(default)]
+    pub watch: WatchConfig,
+    /// 多账号配置
+    #[serde(default)]
+    pub profiles: ProfilesConfig,
+    /// 流量切换配置
+    #[serde(default)]
+    pub flow_switch: FlowSwitchConfig,
+    /// 流量提醒配置
+    #[serde(default)]
+    pub flow_alert: FlowAlertConfig,
+    /// 密码存储配置
+    #[serde(default)]
+    pub secret: SecretConfig,
+}
+
+impl Default for ConfigData {
+    fn default() -> Self {
+        Self {
+            config_version: CURRENT_CONFIG_VERSION,
+            account: AccountConfig::default(),
+            network: NetworkConfig::default(),
+            logging: LoggingConfig::default(),
+            settings: SettingsConfig::default(),
+            message: MessageConfig::default(),
+            watch: WatchConfig::default(),
+            profiles: ProfilesConfig::default(),
+            flow_switch: FlowSwitchConfig::default(),
+            flow_alert: FlowAlertConfig::default(),
+            secret: SecretConfig::default(),
+        }
+    }
 }
 
 impl ConfigData {
@@ -151,6 +271,142 @@ impl ConfigData {
     pub fn load_existing_or_default() -> Self {
         load_config().unwrap_or_default()
     }
+
+    /// 切换默认账号档案
+    ///
+    /// 先把`[account]`中的修改写回当前默认档案，再把目标档案的账号复制到`[account]`
+    pub fn switch_profile(&mut self, name: &str) -> AppResult<()> {
+        let account = self
+            .profiles
+            .get(name)
+            .map(AccountProfile::to_account)
+            .ok_or_else(|| AppError::ConfigError(format!("账号档案不存在: {}", name)))?;
+
+        self.sync_default_profile();
+        self.account = account;
+        self.profiles.default = name.to_string();
+        Ok(())
+    }
+
+    /// 添加或替换账号档案，替换默认档案时同步更新`[account]`
+    pub fn set_profile(&mut self, profile: AccountProfile) {
+        if profile.name == self.profiles.default {
+            self.account = profile.to_account();
+        }
+
+        match self
+            .profiles
+            .accounts
+            .iter_mut()
+            .find(|existing| existing.name == profile.name)
+        {
+            Some(existing) => *existing = profile,
+            None => self.profiles.accounts.push(profile),
+        }
+    }
+
+    /// 把`[account]`中的账号写回默认档案
+    pub fn sync_default_profile(&mut self) {
+        let default = self.profiles.default.clone();
+        if let Some(profile) = self
+            .profiles
+            .accounts
+            .iter_mut()
+            .find(|profile| profile.name == default)
+        {
+            profile.username = self.account.username.clone();
+            profile.encrypted_password = self.account.encrypted_password.clone();
+            profile.isp = self.account.isp.clone();
+        }
+    }
+
+    /// 配置中已保存密码的密文格式，用于加密新账号的密码
+    ///
+    /// 依次查看`[account]`和各档案，都没有保存密码时使用机器密钥加密的格式
+    pub fn password_format(&self) -> CiphertextFormat {
+        std::iter::once(&self.account.encrypted_password)
+            .chain(
+                self.profiles
+                    .accounts
+                    .iter()
+                    .map(|profile| &profile.encrypted_password),
+            )
+            .find(|encrypted_password| !encrypted_password.is_empty())
+            .map_or(CiphertextFormat::AeadV2, |encrypted_password| {
+                ciphertext_format(encrypted_password)
+            })
+    }
+
+    /// 按登录顺序排列的账号，依次为默认账号和`fallback`中的档案
+    ///
+    /// 返回档案名称和账号，`[account]`未关联档案时名称为空；重复和不完整的档案会被跳过
+    pub fn login_accounts(&self) -> Vec<(String, AccountConfig)> {
+        let mut accounts = vec![(self.profiles.default.clone(), self.account.clone())];
+
+        for name in &self.profiles.fallback {
+            if accounts.iter().any(|(existing, _)| existing == name) {
+                continue;
+            }
+            if let Some(profile) = self.profiles.get(name)
+                && !profile.username.is_empty()
+                && self.secret.has_password(&profile.encrypted_password)
+            {
+                accounts.push((name.clone(), profile.to_account()));
+            }
+        }
+
+        accounts
+    }
+
+    /// `[account]`和所有档案中保存的密码
+    fn encrypted_passwords_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        std::iter::once(&mut self.account.encrypted_password).chain(
+            self.profiles
+                .accounts
+                .iter_mut()
+                .map(|profile| &mut profile.encrypted_password),
+        )
+    }
+
+    /// 是否有密码使用主密码加密
+    pub fn uses_passphrase(&self) -> bool {
+        let is_passphrase =
+            |password: &str| ciphertext_format(password) == CiphertextFormat::PassphraseV2;
+
+        is_passphrase(&self.account.encrypted_password)
+            || self
+                .profiles
+                .accounts
+                .iter()
+                .any(|profile| is_passphrase(&profile.encrypted_password))
+    }
+
+    /// 解密所有已保存的密码，再用`wrap`重新加密，返回重新加密的密码数量
+    ///
+    /// 用于在机器密钥和主密码之间切换；任一密码解密或加密失败时不修改配置
+    pub fn rewrap_passwords(
+        &mut self,
+        wrap: impl Fn(&str) -> AppResult<String>,
+    ) -> AppResult<usize> {
+        let mut rewrapped = Vec::new();
+        for encrypted_password in self.encrypted_passwords_mut() {
+            if encrypted_password.is_empty() {
+                rewrapped.push(None);
+            } else {
+                let password = decrypt_config_password(encrypted_password)?;
+                rewrapped.push(Some(wrap(password.expose())?));
+            }
+        }
+
+        let mut count = 0;
+        for (encrypted_password, new_password) in self.encrypted_passwords_mut().zip(rewrapped) {
+            if let Some(new_password) = new_password {
+                *encrypted_password = new_password;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
 }
 
 /// 配置管理器，用于管理配置的保存操作
@@ -197,160 +453,223 @@ impl Default for ConfigManager {
 }
 
 /// 获取配置文件路径
-pub fn get_config_path() -> String {
-    CONFIG_FILE_NAME.to_string()
+///
+/// 查找顺序见[`paths`]模块
+pub fn get_config_path() -> PathBuf {
+    paths::config_location().path
 }
 
 /// 加载配置
 pub fn load_config() -> AppResult<ConfigData> {
+    load_config_with_report().map(|(config, _)| config)
+}
+
+/// 加载配置并返回加载过程中对配置所做修改的报告
+///
+/// 旧版本的配置文件会先备份再按版本依次迁移，迁移或同步开机自启配置后写回配置文件
+pub fn load_config_with_report() -> AppResult<(ConfigData, MigrationReport)> {
     let config_path = get_config_path();
 
-    if !Path::new(&config_path).exists() {
+    if !config_path.exists() {
         let default_config = ConfigData::default();
         save_config(&default_config)?;
-        return Ok(default_config);
+        let report = MigrationReport {
+            from_version: CURRENT_CONFIG_VERSION,
+            to_version: CURRENT_CONFIG_VERSION,
+            ..Default::default()
+        };
+        return Ok((default_config, report));
     }
 
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| AppError::ConfigError(format!("无法读取配置文件 '{}': {}", config_path, e)))?;
+    let content = fs::read_to_string(&config_path).map_err(|e| {
+        AppError::ConfigError(format!(
+            "无法读取配置文件 '{}': {}",
+            config_path.display(),
+            e
+        ))
+    })?;
 
-    let mut config: ConfigData = toml::from_str(&content)
+    let mut document = content
+        .parse::<DocumentMut>()
         .map_err(|e| AppError::ConfigError(format!("配置文件格式错误: {}", e)))?;
 
-    let mut config_modified = false;
-
-    // 如果配置文件中没有message节，追加默认配置
-    if !content.contains("[message]") {
-        // 根据运营商类型设置不同的默认消息配置
-        config.message = if config.account.isp.is_empty() {
-            // 校园网运营商（isp为空）
-            MessageConfig::for_campus_network()
-        } else {
-            // 非校园网运营商
-            MessageConfig::for_non_campus_network()
-        };
-        config_modified = true;
-    } else {
-        // 处理转义的换行符：将 \n 转换回实际的换行符
-        config.message.notify_text = config.message.notify_text.replace("\\n", "\n");
-        config.message.gui_text = config.message.gui_text.replace("\\n", "\n");
-        config.message.log_text = config.message.log_text.replace("\\n", "\n");
+    let mut report = migration::migrate(&mut document)?;
+    if report.is_migrated() {
+        report.backup_path = Some(migration::backup_config_file(
+            &config_path,
+            report.from_version,
+        )?);
     }
 
+    let mut config: ConfigData = toml::from_str(&document.to_string())
+        .map_err(|e| AppError::ConfigError(format!("配置文件格式错误: {}", e)))?;
+
     // 验证并同步开机自启配置
-    #[cfg(windows)]
-    {
-        if validate_and_sync_auto_start_config(&mut config) {
-            config_modified = true;
-        }
+    if validate_and_sync_auto_start_config(&mut config) {
+        report.changes.push("开机自启配置已与系统同步".to_string());
+    }
+
+    if upgrade_legacy_passwords(&mut config) {
+        report
+            .changes
+            .push("账号密码已重新加密为AES-GCM格式".to_string());
     }
 
-    // 如果修改了配置（添加了消息配置节或同步了开机自启配置），则保存配置
-    if config_modified {
-        save_config(&config)?;
+    // 迁移或同步开机自启配置后，在迁移后的文档基础上保存，保留原有注释
+    if report.is_migrated() || report.has_changes() {
+        let content = render_config(&config, Some(&document.to_string()))?;
+        write_atomically(&config_path, &content).map_err(|e| {
+            AppError::ConfigError(format!(
+                "无法写入配置文件 '{}': {}",
+                config_path.display(),
+                e
+            ))
+        })?;
     }
 
-    Ok(config)
+    Ok((config, report))
+}
+
+/// 把旧版AES-CBC格式加密的密码重新加密为当前格式，返回是否有修改
+///
+/// 无法解密的密码保持不变，登录时再提示重新输入
+fn upgrade_legacy_passwords(config: &mut ConfigData) -> bool {
+    let mut upgraded = false;
+    for encrypted_password in config.encrypted_passwords_mut() {
+        if let Ok(Some(new_password)) = upgrade_machine_key_ciphertext(encrypted_password) {
+            *encrypted_password = new_password;
+            upgraded = true;
+        }
+    }
+    upgraded
 }
 
 /// 保存配置
+///
+/// 使用serde序列化配置，已有配置文件时在原文件基础上更新，保留用户添加的注释和节的顺序；
+/// 先写入同目录下的临时文件再重命名覆盖，写入中途崩溃也不会留下不完整的配置文件
 pub fn save_config(config: &ConfigData) -> AppResult<()> {
+    // `[account]`可能在GUI或命令行中被修改，保存前同步到默认档案
+    let mut config = config.clone();
+    config.sync_default_profile();
+    let config = &config;
+
     let config_path = get_config_path();
+    let path = config_path.as_path();
 
-    if let Some(parent) = Path::new(&config_path).parent() {
+    if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| AppError::ConfigError(format!("无法创建配置目录: {}", e)))?;
     }
 
-    let mut content = String::new();
+    let existing = fs::read_to_string(path).ok();
+    let content = render_config(config, existing.as_deref())?;
 
-    // 账户配置
-    content.push_str("[account]\n");
-    content.push_str(&format!("username = \"{}\"\n", config.account.username));
-    content.push_str(&format!(
-        "encrypted_password = \"{}\"\n",
-        config.account.encrypted_password
-    ));
-    content.push_str(&format!("isp = \"{}\"\n\n", config.account.isp));
-
-    // 网络配置
-    content.push_str("[network]\n");
-    content.push_str(&format!("login_ip = \"{}\"\n", config.network.login_ip));
-    content.push_str(&format!(
-        "result_return = \'{}\'\n",
-        config.network.result_return
-    ));
-    content.push_str(&format!(
-        "signed_in_title = \"{}\"\n",
-        config.network.signed_in_title
-    ));
-    content.push_str(&format!(
-        "not_sign_in_title = \"{}\"\n\n",
-        config.network.not_sign_in_title
-    ));
+    write_atomically(path, &content).map_err(|e| {
+        AppError::ConfigError(format!(
+            "无法写入配置文件 '{}': {}",
+            config_path.display(),
+            e
+        ))
+    })
+}
 
-    // 日志配置
-    content.push_str("[logging]\n");
-    content.push_str(&format!(
-        "enable_logging = {}\n",
-        config.logging.enable_logging
-    ));
-    content.push_str(&format!(
-        "log_file_path = \"{}\"\n",
-        config.logging.log_file_path
-    ));
-    content.push_str(&format!(
-        "info_log_retention_days = {}\n\n",
-        config.logging.info_log_retention_days
-    ));
+/// 将配置序列化为TOML文本
+///
+/// `existing`为现有配置文件内容，能够解析时只更新其中的值，保留注释、空行和键的顺序
+fn render_config(config: &ConfigData, existing: Option<&str>) -> AppResult<String> {
+    let content = toml::to_string(config)
+        .map_err(|e| AppError::ConfigError(format!("无法序列化配置: {}", e)))?;
 
-    content.push_str("[settings]\n");
-    content.push_str(&format!("auto_start = {}\n\n", config.settings.auto_start));
+    let Some(mut document) = existing.and_then(|text| text.parse::<DocumentMut>().ok()) else {
+        return Ok(content);
+    };
 
-    content.push_str("[message]\n");
-    content.push_str(&format!(
-        "notify_text = \"{}\"\n",
-        config.message.notify_text.replace("\n", "\\n")
-    ));
-    content.push_str(&format!(
-        "gui_text = \"{}\"\n",
-        config.message.gui_text.replace("\n", "\\n")
-    ));
-    content.push_str(&format!(
-        "log_text = \"{}\"\n",
-        config.message.log_text.replace("\n", "\\n")
+    let updated = content
+        .parse::<DocumentMut>()
+        .map_err(|e| AppError::ConfigError(format!("无法序列化配置: {}", e)))?;
+    merge_table(document.as_table_mut(), updated.as_table());
+
+    Ok(document.to_string())
+}
+
+/// 用新配置的值更新现有表，保留现有条目的注释和格式
+fn merge_table(existing: &mut Table, updated: &Table) {
+    // 删除新配置中已不存在的键（如被清空的可选项）
+    let removed: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !updated.contains_key(key))
+        .collect();
+    for key in removed {
+        existing.remove(&key);
+    }
+
+    for (key, item) in updated.iter() {
+        match (existing.get_mut(key), item) {
+            (Some(Item::Table(old)), Item::Table(new)) => merge_table(old, new),
+            (Some(Item::Value(old)), Item::Value(new)) => replace_value(old, new.clone()),
+            // 现有文件使用内联数组书写表数组时保持内联写法
+            (Some(Item::Value(old)), Item::ArrayOfTables(new)) => {
+                replace_value(old, Value::Array(new.clone().into_array()))
+            }
+            (Some(old), new) => *old = new.clone(),
+            (None, new) => {
+                existing.insert(key, new.clone());
+            }
+        }
+    }
+}
+
+/// 替换值并保留原有的前后注释
+fn replace_value(old: &mut Value, mut new: Value) {
+    *new.decor_mut() = old.decor().clone();
+    *old = new;
+}
+
+/// 先写入临时文件再重命名覆盖目标文件
+fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| CONFIG_FILE_NAME.to_string());
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
     ));
 
-    fs::write(&config_path, content)
-        .map_err(|e| AppError::ConfigError(format!("无法写入配置文件 '{}': {}", config_path, e)))?;
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
 
-    Ok(())
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
 }
 
 /// 检查配置是否完整
 pub fn is_config_complete(config: &ConfigData) -> bool {
-    !config.account.username.is_empty() && !config.account.encrypted_password.is_empty()
+    !config.account.username.is_empty()
+        && config
+            .secret
+            .has_password(&config.account.encrypted_password)
 }
 
 /// 验证消息配置是否合法
 pub fn validate_message_config(config: &MessageConfig) -> bool {
-    let templates = [&config.notify_text, &config.gui_text, &config.log_text];
-
     // 检查所有模板中是否至少有一个包含占位符
-    let mut has_placeholder = false;
-
-    for template in templates {
-        if template.contains("%1")
-            || template.contains("%2")
-            || template.contains("%3")
-            || template.contains("%4")
-        {
-            has_placeholder = true;
-            break;
-        }
-    }
-
-    has_placeholder
+    [&config.notify_text, &config.gui_text, &config.log_text]
+        .iter()
+        .any(|template| MessageTemplate::parse(template).has_placeholder())
 }
 
 lazy_static! {
@@ -361,10 +680,44 @@ pub fn get_global_config() -> std::sync::MutexGuard<'static, ConfigData> {
     CONFIG.lock().unwrap()
 }
 
+/// GUI可执行文件名
+const GUI_EXECUTABLE_NAME: &str = "AutoLoginGUET";
+
+/// 获取开机自启时执行的可执行文件
+///
+/// GUI和命令行是两个可执行文件，无论由哪个设置开机自启都优先注册同目录下的GUI，
+/// 仅构建了命令行程序时使用命令行程序
+pub fn auto_start_executable() -> Option<PathBuf> {
+    let current_exe = env::current_exe().ok()?;
+    let gui_exe = current_exe.with_file_name(format!(
+        "{}{}",
+        GUI_EXECUTABLE_NAME,
+        env::consts::EXE_SUFFIX
+    ));
+
+    if gui_exe.is_file() {
+        Some(gui_exe)
+    } else {
+        Some(current_exe)
+    }
+}
+
+/// 获取开机自启时执行的命令
+pub fn auto_start_command() -> Option<String> {
+    let exe_path = auto_start_executable()?;
+    Some(format!(
+        "\"{}\" -silent{}",
+        exe_path.to_str()?,
+        paths::auto_start_config_arg()
+    ))
+}
+
 /// 检查注册表中开机自启项是否存在
+///
+/// 只检查`AutoLoginGuet`值是否存在，不比较可执行文件路径，
+/// 否则由GUI设置的开机自启会在命令行程序加载配置时被误判为已关闭
 #[cfg(windows)]
 pub fn is_auto_start_registry_exists() -> bool {
-    use std::env;
     use winreg::RegKey;
     use winreg::enums::*;
 
@@ -374,18 +727,31 @@ pub fn is_auto_start_registry_exists() -> bool {
         "AutoLoginGuet",
     );
 
-    if let Ok(reg_key) = hkcu.open_subkey_with_flags(reg_path, KEY_READ)
-        && let Ok(value) = reg_key.get_value::<String, _>(app_name)
-        && let Ok(exe_path) = env::current_exe() {
-            let expected_value =
-            format!("\"{}\" -silent", exe_path.to_str().unwrap_or_default());
-            return value == expected_value;
-        }
-    false
+    hkcu.open_subkey_with_flags(reg_path, KEY_READ)
+        .and_then(|reg_key| reg_key.get_value::<String, _>(app_name))
+        .is_ok()
 }
 
-/// 验证并同步开机自启配置，与注册表状态一致
-#[cfg(windows)]
+/// 获取XDG自启动项文件路径（`$XDG_CONFIG_HOME/autostart/AutoLoginGuet.desktop`）
+#[cfg(not(windows))]
+pub fn auto_start_entry_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join("autostart").join("AutoLoginGuet.desktop"))
+}
+
+/// 检查XDG自启动项是否存在
+///
+/// 与Windows平台保持相同的函数名，便于GUI和配置模块统一调用
+#[cfg(not(windows))]
+pub fn is_auto_start_registry_exists() -> bool {
+    auto_start_entry_path().is_some_and(|entry_path| entry_path.is_file())
+}
+
+/// 验证并同步开机自启配置，与注册表（或XDG自启动项）状态一致
 pub fn validate_and_sync_auto_start_config(config: &mut ConfigData) -> bool {
     let registry_exists = is_auto_start_registry_exists();
 
@@ -399,3 +765,63 @@ pub fn validate_and_sync_auto_start_config(config: &mut ConfigData) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_format_follows_saved_passwords() {
+        let mut config = ConfigData::default();
+        assert_eq!(config.password_format(), CiphertextFormat::AeadV2);
+
+        config.profiles.accounts.push(AccountProfile {
+            name: "isp".to_string(),
+            username: "2100000000".to_string(),
+            encrypted_password: "pp2:AAAA".to_string(),
+            isp: "cmcc".to_string(),
+        });
+        assert_eq!(config.password_format(), CiphertextFormat::PassphraseV2);
+
+        config.account.encrypted_password = "v2:AAAA".to_string();
+        assert_eq!(config.password_format(), CiphertextFormat::AeadV2);
+    }
+
+    #[test]
+    fn test_render_config_round_trip_and_keep_comments() {
+        let mut config = ConfigData::default();
+        config.account.username = "user\"name\\".to_string();
+        config.logging.log_file_path = "C:\\Users\\guet\\AutoLogin.log".to_string();
+        config.message.notify_text = "%1 \"%2\"\n%3 %4".to_string();
+
+        let content = render_config(&config, None).unwrap();
+        let parsed: ConfigData = toml::from_str(&content).unwrap();
+        assert_eq!(parsed, config);
+
+        let existing =
+            "# 校园网账号\n[account]\nusername = \"old\" # 学号\n\n[settings]\nauto_start = true\n";
+        let content = render_config(&config, Some(existing)).unwrap();
+        assert!(content.starts_with("config_version = 2\n# 校园网账号\n[account]\n"));
+        assert!(content.contains("# 学号"));
+        let parsed: ConfigData = toml::from_str(&content).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_validate_message_config() {
+        assert!(validate_message_config(&MessageConfig::for_campus_network()));
+        assert!(validate_message_config(
+            &MessageConfig::for_non_campus_network()
+        ));
+
+        let mut config = MessageConfig {
+            notify_text: "登录完成".to_string(),
+            gui_text: "{name}".to_string(),
+            log_text: String::new(),
+        };
+        assert!(!validate_message_config(&config));
+
+        config.log_text = "%1".to_string();
+        assert!(validate_message_config(&config));
+    }
+}
@@ -1,34 +1,99 @@
 //! 密码加密解密模块
+//!
+//! 密文格式：
+//! - `v2:<base64>`：Argon2id派生密钥，AES-256-GCM加密，base64内容为`盐(16字节) | nonce(12字节) | 密文和认证标签`，
+//!   版本前缀作为附加认证数据，密文被篡改时解密必定失败
+//! - `pp2:<base64>`：与`v2:`格式相同，但密钥由主密码而不是机器标识派生，见`passphrase`模块
+//! - 无前缀的base64：旧版格式，SHA-256派生密钥，AES-256-CBC加密，仅用于读取旧配置，
+//!   加载配置时会重新加密为新格式
 
 use crate::core::error::{AppError, AppResult};
 use crate::core::events::{EventBus, notify_login_attempted, notify_network_status_checked};
+use crate::core::machine_key::{default_providers, resolve_machine_id};
 use crate::core::message::{CampusNetworkStatus, WanStatus};
-use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+use crate::core::passphrase::{PASSPHRASE_ENV, session_passphrase};
+use crate::core::secret_string::SecretString;
+use aes::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{Engine as _, engine::general_purpose};
 use rand::RngCore;
 use sha2::{Digest, Sha256};
-#[cfg(windows)]
-use winreg::RegKey;
-#[cfg(windows)]
-use winreg::enums::*;
+use zeroize::Zeroize;
 
-type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
 type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
 
-/// 从密码派生密钥
-fn derive_key(password: &str) -> [u8; 32] {
+/// 当前密文格式的版本前缀
+const CIPHERTEXT_PREFIX_V2: &str = "v2:";
+
+/// 主密码加密的密文的版本前缀
+const CIPHERTEXT_PREFIX_PASSPHRASE_V2: &str = "pp2:";
+
+/// 盐长度
+const SALT_LEN: usize = 16;
+
+/// AES-GCM的nonce长度
+const NONCE_LEN: usize = 12;
+
+/// Argon2id参数：内存19MiB、迭代2次、并行度1
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+
+/// 密文格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CiphertextFormat {
+    /// 旧版AES-256-CBC格式，没有完整性校验
+    LegacyCbc,
+    /// AES-256-GCM格式
+    AeadV2,
+    /// 主密码加密的AES-256-GCM格式
+    PassphraseV2,
+}
+
+/// 判断密文格式
+pub fn ciphertext_format(encrypted_password: &str) -> CiphertextFormat {
+    if encrypted_password.starts_with(CIPHERTEXT_PREFIX_V2) {
+        CiphertextFormat::AeadV2
+    } else if encrypted_password.starts_with(CIPHERTEXT_PREFIX_PASSPHRASE_V2) {
+        CiphertextFormat::PassphraseV2
+    } else {
+        CiphertextFormat::LegacyCbc
+    }
+}
+
+/// 使用Argon2id从密钥字符串和盐派生加密密钥
+fn derive_key(key: &str, salt: &[u8]) -> AppResult<[u8; 32]> {
+    let params = Params::new(
+        ARGON2_MEMORY_KIB,
+        ARGON2_ITERATIONS,
+        ARGON2_PARALLELISM,
+        Some(32),
+    )
+    .map_err(|e| AppError::CryptoError(format!("密钥派生参数错误: {}", e)))?;
+
+    let mut derived = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(key.as_bytes(), salt, &mut derived)
+        .map_err(|e| AppError::CryptoError(format!("密钥派生失败: {}", e)))?;
+    Ok(derived)
+}
+
+/// 旧版格式的密钥派生：单次SHA-256
+fn derive_legacy_key(key: &str) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(password.as_bytes());
+    hasher.update(key.as_bytes());
     hasher.update(b"AutoLoginGUET_SALT_2025");
     let result = hasher.finalize();
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&result);
-    key
+    let mut derived = [0u8; 32];
+    derived.copy_from_slice(&result);
+    derived
 }
 
 /// 加密密码
 ///
-/// 使用AES-CBC算法加密密码，需要提供加密密钥
+/// 使用Argon2id派生密钥、AES-256-GCM加密，输出`v2:`格式的密文
 ///
 /// # 参数
 /// * `password` - 需要加密的明文密码
@@ -37,27 +102,47 @@ fn derive_key(password: &str) -> [u8; 32] {
 /// # 返回值
 /// 返回加密后的密码字符串，或包含错误信息的AppError
 pub fn encrypt_password(password: &str, key: &str) -> AppResult<String> {
-    let key = derive_key(key);
-    let mut iv = [0u8; 16];
-    rand::rng().fill_bytes(&mut iv);
+    encrypt_aead(password, key, CIPHERTEXT_PREFIX_V2)
+}
+
+/// 使用主密码加密密码，输出`pp2:`格式的密文
+pub fn encrypt_password_with_passphrase(password: &str, passphrase: &str) -> AppResult<String> {
+    encrypt_aead(password, passphrase, CIPHERTEXT_PREFIX_PASSPHRASE_V2)
+}
 
-    let cipher = Aes256CbcEnc::new(&key.into(), &iv.into());
-    let mut buffer = vec![0u8; password.len() + 16];
-    buffer[..password.len()].copy_from_slice(password.as_bytes());
+/// 加密并输出带`prefix`前缀的密文，前缀同时作为附加认证数据
+fn encrypt_aead(password: &str, key: &str, prefix: &str) -> AppResult<String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    rand::rng().fill_bytes(&mut nonce);
 
+    let derived = derive_key(key, &salt)?;
+    let cipher = Aes256Gcm::new(&derived.into());
     let ciphertext = cipher
-        .encrypt_padded_mut::<Pkcs7>(&mut buffer, password.len())
-        .map_err(|e| AppError::CryptoError(format!("加密失败: {:?}", e)))?;
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: password.as_bytes(),
+                aad: prefix.as_bytes(),
+            },
+        )
+        .map_err(|e| AppError::CryptoError(format!("加密失败: {}", e)))?;
 
-    let mut result = Vec::with_capacity(iv.len() + ciphertext.len());
-    result.extend_from_slice(&iv);
-    result.extend_from_slice(ciphertext);
-    Ok(general_purpose::STANDARD.encode(&result))
+    let mut result = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+    result.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "{}{}",
+        prefix,
+        general_purpose::STANDARD.encode(&result)
+    ))
 }
 
 /// 解密密码
 ///
-/// 使用AES-CBC算法解密密码，需要提供解密密钥
+/// 根据密文前缀选择格式，支持`v2:`、`pp2:`格式和旧版CBC格式
 ///
 /// # 参数
 /// * `encrypted_password` - 需要解密的密文密码
@@ -65,8 +150,47 @@ pub fn encrypt_password(password: &str, key: &str) -> AppResult<String> {
 ///
 /// # 返回值
 /// 返回解密后的明文密码，或包含错误信息的AppError
-pub fn decrypt_password(encrypted_password: &str, key: &str) -> AppResult<String> {
-    let key = derive_key(key);
+pub fn decrypt_password(encrypted_password: &str, key: &str) -> AppResult<SecretString> {
+    if let Some(encoded) = encrypted_password.strip_prefix(CIPHERTEXT_PREFIX_V2) {
+        decrypt_aead(encoded, key, CIPHERTEXT_PREFIX_V2)
+    } else if let Some(encoded) = encrypted_password.strip_prefix(CIPHERTEXT_PREFIX_PASSPHRASE_V2) {
+        decrypt_aead(encoded, key, CIPHERTEXT_PREFIX_PASSPHRASE_V2)
+    } else {
+        decrypt_legacy_cbc(encrypted_password, key)
+    }
+}
+
+/// 解密带`prefix`前缀的密文（`encoded`不含前缀）
+fn decrypt_aead(encoded: &str, key: &str, prefix: &str) -> AppResult<SecretString> {
+    let data = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| AppError::CryptoError(format!("Base64解码失败: {:?}", e)))?;
+
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(AppError::CryptoError("数据长度不足".into()));
+    }
+
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let derived = derive_key(key, salt)?;
+    let cipher = Aes256Gcm::new(&derived.into());
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: prefix.as_bytes(),
+            },
+        )
+        .map_err(|_| AppError::CryptoError("解密失败: 密钥错误或密文已被修改".into()))?;
+
+    into_secret(plaintext)
+}
+
+/// 解密旧版AES-CBC格式的密文
+fn decrypt_legacy_cbc(encrypted_password: &str, key: &str) -> AppResult<SecretString> {
+    let key = derive_legacy_key(key);
     let data = general_purpose::STANDARD
         .decode(encrypted_password)
         .map_err(|e| AppError::CryptoError(format!("Base64解码失败: {:?}", e)))?;
@@ -81,39 +205,49 @@ pub fn decrypt_password(encrypted_password: &str, key: &str) -> AppResult<String
     let mut buffer = vec![0u8; ciphertext.len()];
     buffer[..ciphertext.len()].copy_from_slice(ciphertext);
 
-    let plaintext = cipher
-        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
-        .map_err(|e| AppError::CryptoError(format!("解密失败: {:?}", e)))?;
+    let result = match cipher.decrypt_padded_mut::<Pkcs7>(&mut buffer) {
+        Ok(plaintext) => into_secret(plaintext.to_vec()),
+        Err(e) => Err(AppError::CryptoError(format!("解密失败: {:?}", e))),
+    };
+    buffer.zeroize();
+    result
+}
 
-    String::from_utf8(plaintext.to_vec())
-        .map_err(|e| AppError::CryptoError(format!("UTF-8解码失败: {:?}", e)))
+/// 把解密得到的明文转换为`SecretString`
+///
+/// 明文不是有效的UTF-8时清零后返回错误，错误信息中不包含明文内容
+fn into_secret(plaintext: Vec<u8>) -> AppResult<SecretString> {
+    String::from_utf8(plaintext)
+        .map(SecretString::new)
+        .map_err(|e| {
+            let error = e.utf8_error();
+            e.into_bytes().zeroize();
+            AppError::CryptoError(format!("UTF-8解码失败: {}", error))
+        })
 }
 
-#[cfg(windows)]
-/// 获取Windows机器GUID
-fn get_machine_guid() -> AppResult<String> {
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-    let key = hklm
-        .open_subkey("SOFTWARE\\Microsoft\\Cryptography")
-        .map_err(|e| AppError::CryptoError(format!("无法打开注册表项: {}", e)))?;
-    let machine_guid: String = key
-        .get_value("MachineGuid")
-        .map_err(|e| AppError::CryptoError(format!("无法获取MachineGuid值: {}", e)))?;
-    Ok(machine_guid)
+/// 把旧版格式的机器密钥密文重新加密为当前格式
+///
+/// 已是当前格式或为空时返回`None`；旧版密文无法解密时返回错误，原密文保持不变
+pub fn upgrade_machine_key_ciphertext(encrypted_password: &str) -> AppResult<Option<String>> {
+    if encrypted_password.is_empty()
+        || ciphertext_format(encrypted_password) != CiphertextFormat::LegacyCbc
+    {
+        return Ok(None);
+    }
+
+    let machine_key = generate_machine_key()?;
+    let password = decrypt_legacy_cbc(encrypted_password, &machine_key)?;
+    encrypt_password(password.expose(), &machine_key).map(Some)
 }
 
-#[cfg(windows)]
 /// 生成机器相关的密钥（基于机器信息）
 ///
-/// 用于生成与机器绑定的加密密钥
-/// 如果无法获取机器信息，则使用默认密钥
-pub fn generate_machine_key() -> String {
-    match get_machine_guid() {
-        Ok(guid) => {
-            format!("AutoLoginGUET_salt_2025_{}", guid)
-        }
-        Err(_) => "AutoLoginGUET_default_key_2025".to_string(),
-    }
+/// 用于生成与机器绑定的加密密钥，机器标识的读取顺序见`machine_key`模块
+/// 如果无法获取任何机器标识，则返回`CryptoError`
+pub fn generate_machine_key() -> AppResult<String> {
+    let machine_id = resolve_machine_id(&default_providers())?;
+    Ok(format!("AutoLoginGUET_salt_2025_{}", machine_id))
 }
 
 /// 使用机器密钥加密密码
@@ -126,7 +260,7 @@ pub fn generate_machine_key() -> String {
 /// # 返回值
 /// 返回加密后的密码字符串，或包含错误信息的AppError
 pub fn encrypt_password_with_machine_key(password: &str) -> AppResult<String> {
-    let machine_key = generate_machine_key();
+    let machine_key = generate_machine_key()?;
     encrypt_password(password, &machine_key)
 }
 
@@ -139,28 +273,80 @@ pub fn encrypt_password_with_machine_key(password: &str) -> AppResult<String> {
 ///
 /// # 返回值
 /// 返回解密后的明文密码，或包含错误信息的AppError
-pub fn decrypt_password_with_machine_key(encrypted_password: &str) -> AppResult<String> {
-    let machine_key = generate_machine_key();
+pub fn decrypt_password_with_machine_key(encrypted_password: &str) -> AppResult<SecretString> {
+    let machine_key = generate_machine_key()?;
     decrypt_password(encrypted_password, &machine_key)
 }
 
+/// 按已有密文的密钥类型加密新密码
+///
+/// 已有密文为`pp2:`格式时使用主密码加密，本次运行没有提供主密码时返回错误，
+/// 避免新密码改用机器密钥加密后配置中混用两种密钥，否则使用机器密钥加密
+pub fn encrypt_password_like(password: &str, existing: &str) -> AppResult<String> {
+    encrypt_password_as(
+        password,
+        ciphertext_format(existing),
+        session_passphrase().as_ref(),
+    )
+}
+
+/// 按指定的密文格式加密新密码
+///
+/// `format`为`PassphraseV2`时使用`passphrase`加密，未提供主密码时返回错误；
+/// 其他格式使用机器密钥加密
+pub fn encrypt_password_as(
+    password: &str,
+    format: CiphertextFormat,
+    passphrase: Option<&SecretString>,
+) -> AppResult<String> {
+    if format != CiphertextFormat::PassphraseV2 {
+        return encrypt_password_with_machine_key(password);
+    }
+
+    let passphrase = passphrase.ok_or_else(|| {
+        AppError::ConfigError(format!(
+            "密码使用主密码加密，保存新密码前请输入主密码或设置环境变量{}",
+            PASSPHRASE_ENV
+        ))
+    })?;
+    encrypt_password_with_passphrase(password, passphrase.expose())
+}
+
 /// 解密配置中的密码
 ///
-/// 专门用于解密配置文件中存储的密码，会将`CryptoError`转换为`PasswordDecryptionError`
+/// 专门用于解密配置文件中存储的密码，会将`CryptoError`转换为`PasswordDecryptionError`。
+/// `pp2:`格式使用本次运行的主密码解密，缺少主密码或主密码错误时返回`PasswordDecryptionError`；
+/// 其他格式使用机器密钥解密，无法获取机器标识时直接返回`CryptoError`，便于与密码损坏区分
 ///
 /// # 参数
 /// * `encrypted_password` - 需要解密的密文密码
 ///
 /// # 返回值
 /// 返回解密后的明文密码，或包含错误信息的AppError
-pub fn decrypt_config_password(encrypted_password: &str) -> AppResult<String> {
-    let machine_key = generate_machine_key();
-    decrypt_password(encrypted_password, &machine_key).map_err(|e| {
+pub fn decrypt_config_password(encrypted_password: &str) -> AppResult<SecretString> {
+    let (key, user_msg) = if ciphertext_format(encrypted_password) == CiphertextFormat::PassphraseV2
+    {
+        let passphrase = session_passphrase().ok_or_else(|| AppError::PasswordDecryptionError {
+            internal_msg: "密码使用主密码加密，但未提供主密码".to_string(),
+            user_msg: format!(
+                "密码使用主密码加密，请输入主密码或设置环境变量{}",
+                PASSPHRASE_ENV
+            ),
+        })?;
+        (passphrase, "主密码错误或密码已被修改")
+    } else {
+        (
+            SecretString::new(generate_machine_key()?),
+            "密码解密失败，请重新输入密码",
+        )
+    };
+
+    decrypt_password(encrypted_password, key.expose()).map_err(|e| {
         match e {
             // 将加密错误转换为密码解密错误，隐藏内部细节
             AppError::CryptoError(internal_msg) => AppError::PasswordDecryptionError {
                 internal_msg,
-                user_msg: "密码解密失败，请重新输入密码".to_string(),
+                user_msg: user_msg.to_string(),
             },
             _ => e,
         }
@@ -191,10 +377,10 @@ pub fn generate_encrypted_password(password: &str) -> String {
 /// # 返回值
 /// 返回解密后的密码，如果解密失败则返回空字符串
 pub fn handle_password_decryption_error_with_default(
-    result: Result<String, AppError>,
+    result: Result<SecretString, AppError>,
     event_bus: &EventBus,
-) -> String {
-    handle_password_decryption_error(result, event_bus).unwrap_or_else(|_| String::new())
+) -> SecretString {
+    handle_password_decryption_error(result, event_bus).unwrap_or_default()
 }
 
 /// 统一处理密码解密错误
@@ -209,9 +395,9 @@ pub fn handle_password_decryption_error_with_default(
 /// # 返回值
 /// 返回解密后的密码，如果解密失败则返回包含错误信息的AppError
 pub fn handle_password_decryption_error(
-    decrypt_result: Result<String, AppError>,
+    decrypt_result: Result<SecretString, AppError>,
     event_bus: &EventBus,
-) -> Result<String, AppError> {
+) -> Result<SecretString, AppError> {
     match decrypt_result {
         Ok(password) => Ok(password),
         Err(e) => match &e {
@@ -251,3 +437,81 @@ pub fn handle_password_decryption_error(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+
+    type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+
+    /// 按旧版格式加密，用于验证兼容性
+    fn encrypt_legacy_cbc(password: &str, key: &str) -> String {
+        let key = derive_legacy_key(key);
+        let iv = [7u8; 16];
+        let mut buffer = vec![0u8; password.len() + 16];
+        buffer[..password.len()].copy_from_slice(password.as_bytes());
+        let ciphertext = Aes256CbcEnc::new(&key.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, password.len())
+            .unwrap();
+        general_purpose::STANDARD.encode([&iv[..], ciphertext].concat())
+    }
+
+    #[test]
+    fn test_encrypt_password_detects_tampering_and_reads_legacy_format() {
+        let encrypted = encrypt_password("p@ss word", "key").unwrap();
+        assert_eq!(ciphertext_format(&encrypted), CiphertextFormat::AeadV2);
+        assert_eq!(
+            decrypt_password(&encrypted, "key").unwrap().expose(),
+            "p@ss word"
+        );
+        assert!(decrypt_password(&encrypted, "other key").is_err());
+
+        let mut data = general_purpose::STANDARD
+            .decode(encrypted.strip_prefix(CIPHERTEXT_PREFIX_V2).unwrap())
+            .unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        let tampered = format!(
+            "{}{}",
+            CIPHERTEXT_PREFIX_V2,
+            general_purpose::STANDARD.encode(&data)
+        );
+        assert!(decrypt_password(&tampered, "key").is_err());
+
+        let legacy = encrypt_legacy_cbc("p@ss word", "key");
+        assert_eq!(ciphertext_format(&legacy), CiphertextFormat::LegacyCbc);
+        assert_eq!(
+            decrypt_password(&legacy, "key").unwrap().expose(),
+            "p@ss word"
+        );
+
+        // 主密码格式的前缀参与认证，不能改写为机器密钥格式
+        let wrapped = encrypt_password_with_passphrase("p@ss word", "key").unwrap();
+        assert_eq!(ciphertext_format(&wrapped), CiphertextFormat::PassphraseV2);
+        assert_eq!(
+            decrypt_password(&wrapped, "key").unwrap().expose(),
+            "p@ss word"
+        );
+        let relabeled = wrapped.replacen(CIPHERTEXT_PREFIX_PASSPHRASE_V2, CIPHERTEXT_PREFIX_V2, 1);
+        assert!(decrypt_password(&relabeled, "key").is_err());
+
+        // 没有主密码时不会悄悄改用机器密钥加密
+        assert!(encrypt_password_as("new password", CiphertextFormat::PassphraseV2, None).is_err());
+        let passphrase = SecretString::from("key");
+        let rewrapped = encrypt_password_as(
+            "new password",
+            CiphertextFormat::PassphraseV2,
+            Some(&passphrase),
+        )
+        .unwrap();
+        assert_eq!(
+            ciphertext_format(&rewrapped),
+            CiphertextFormat::PassphraseV2
+        );
+        assert_eq!(
+            decrypt_password(&rewrapped, "key").unwrap().expose(),
+            "new password"
+        );
+    }
+}
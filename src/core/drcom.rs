@@ -0,0 +1,224 @@
+//! Dr.COM认证响应解析模块
+//!
+//! 将`/drcom/login`返回的JSONP（如`dr1003({...})`）解析为结构化结果，
+//! 并把认证服务器的错误码映射为用户可读的登录失败原因
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Dr.COM登录接口的响应内容
+///
+/// 不同版本的认证系统字段类型不完全一致（数字或字符串），解析时统一做宽松处理
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DrcomLoginResponse {
+    /// 登录结果，1表示成功
+    #[serde(deserialize_with = "lenient_i64")]
+    pub result: i64,
+    /// 失败时的返回码，对应Dr.COM页面脚本中的`Msg`
+    #[serde(deserialize_with = "lenient_i64")]
+    pub ret_code: i64,
+    /// 提示信息
+    #[serde(deserialize_with = "lenient_string")]
+    pub msg: String,
+    /// 附加错误信息，如`ldap auth error`
+    #[serde(deserialize_with = "lenient_string")]
+    pub msga: String,
+    /// 登录账号
+    #[serde(deserialize_with = "lenient_string")]
+    pub uid: String,
+    /// 分配的IPv4地址
+    #[serde(deserialize_with = "lenient_string")]
+    pub v46ip: String,
+    /// 分配的IPv6地址
+    #[serde(deserialize_with = "lenient_string")]
+    pub myv6ip: String,
+    /// 在线设备MAC地址
+    #[serde(deserialize_with = "lenient_string")]
+    pub olmac: String,
+    /// 用户组ID
+    #[serde(deserialize_with = "lenient_i64")]
+    pub gid: i64,
+}
+
+impl DrcomLoginResponse {
+    /// 从JSONP文本解析响应，不是JSONP格式时返回None
+    pub fn parse(text: &str) -> Option<Self> {
+        let start = text.find('(')?;
+        let end = text.rfind(')')?;
+        if end <= start {
+            return None;
+        }
+
+        serde_json::from_str(text[start + 1..end].trim()).ok()
+    }
+
+    /// 是否登录成功
+    pub fn is_success(&self) -> bool {
+        self.result == 1
+    }
+
+    /// 登录失败原因，登录成功时返回None
+    pub fn failure_reason(&self) -> Option<LoginFailureReason> {
+        if self.is_success() {
+            return None;
+        }
+
+        let detail = if self.msga.is_empty() {
+            &self.msg
+        } else {
+            &self.msga
+        };
+        Some(LoginFailureReason::classify(self.ret_code, detail))
+    }
+}
+
+/// 登录失败原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginFailureReason {
+    /// 账号或密码错误
+    WrongPassword,
+    /// 账号欠费，或时长、流量超出限制
+    AccountArrears,
+    /// 账号已暂停使用
+    AccountSuspended,
+    /// 在线设备数量已达上限
+    TooManyDevices,
+    /// 运营商账号未绑定
+    IspNotBound,
+    /// 账号只能在指定地址登录
+    AddressRestricted,
+    /// 无法识别的原因，保留服务器返回的原始信息
+    Unknown(String),
+}
+
+impl LoginFailureReason {
+    /// 根据返回码和附加信息判断失败原因
+    ///
+    /// 附加信息只按Dr.COM返回的完整取值匹配（不区分大小写），
+    /// 无法匹配时再按`Msg`返回码判断，都无法识别时保留原始信息
+    pub fn classify(ret_code: i64, detail: &str) -> Self {
+        match detail.trim().to_lowercase().as_str() {
+            "ldap auth error" | "userid error1" | "userid error2" | "userid error3" => {
+                LoginFailureReason::WrongPassword
+            }
+            "rad:limit users err" | "inuse, login again" | "in use" => {
+                LoginFailureReason::TooManyDevices
+            }
+            "rad:status_err" => LoginFailureReason::AccountArrears,
+            // 运营商账号登录时，学号未绑定该运营商账号或绑定信息有误
+            "rad:username_err" => LoginFailureReason::IspNotBound,
+            _ => match ret_code {
+                1 if detail.is_empty() => LoginFailureReason::WrongPassword,
+                2 => LoginFailureReason::TooManyDevices,
+                3 | 11 => LoginFailureReason::AddressRestricted,
+                4 => LoginFailureReason::AccountArrears,
+                5 => LoginFailureReason::AccountSuspended,
+                _ => LoginFailureReason::Unknown(detail.to_string()),
+            },
+        }
+    }
+
+    /// 转换为消息文本
+    pub fn to_message(&self) -> String {
+        match self {
+            LoginFailureReason::WrongPassword => "账号或密码错误".to_string(),
+            LoginFailureReason::AccountArrears => "账号欠费或时长、流量超出限制".to_string(),
+            LoginFailureReason::AccountSuspended => "账号已暂停使用".to_string(),
+            LoginFailureReason::TooManyDevices => {
+                "在线设备数量已达上限，请先下线其他设备".to_string()
+            }
+            LoginFailureReason::IspNotBound => {
+                "运营商账号未绑定，请先在自助服务系统绑定".to_string()
+            }
+            LoginFailureReason::AddressRestricted => "账号只能在指定地址登录".to_string(),
+            LoginFailureReason::Unknown(detail) if detail.is_empty() => "未知原因".to_string(),
+            LoginFailureReason::Unknown(detail) => format!("认证服务器返回: {}", detail),
+        }
+    }
+}
+
+/// 将数字或字符串形式的字段解析为整数
+fn lenient_i64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_i64()
+            .or_else(|| n.as_f64().map(|f| f as i64))
+            .unwrap_or_default(),
+        Value::String(s) => s.trim().parse().unwrap_or_default(),
+        Value::Bool(b) => i64::from(b),
+        _ => 0,
+    })
+}
+
+/// 将任意类型的字段解析为字符串
+fn lenient_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(s) => s,
+        Value::Null => String::new(),
+        other => other.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_drcom_login_response() {
+        let success = DrcomLoginResponse::parse(
+            r#"dr1003({"result":1,"aolno":2315,"m46":0,"v46ip":"10.33.12.34","myv6ip":"","sms":0,"NID":"","olmac":"a1b2c3d4e5f6","gid":13,"uid":"2100000000"});"#,
+        )
+        .unwrap();
+        assert!(success.is_success());
+        assert_eq!(success.v46ip, "10.33.12.34");
+        assert_eq!(success.uid, "2100000000");
+        assert_eq!(success.failure_reason(), None);
+
+        let wrong_password = DrcomLoginResponse::parse(
+            r#"dr1003({"result":"0","msg":"","ret_code":"1","msga":"ldap auth error"})"#,
+        )
+        .unwrap();
+        assert_eq!(
+            wrong_password.failure_reason(),
+            Some(LoginFailureReason::WrongPassword)
+        );
+
+        let too_many = DrcomLoginResponse::parse(
+            r#"dr1003({"result":0,"ret_code":1,"msga":"Rad:Limit Users Err"})"#,
+        )
+        .unwrap();
+        assert_eq!(
+            too_many.failure_reason(),
+            Some(LoginFailureReason::TooManyDevices)
+        );
+
+        let arrears =
+            DrcomLoginResponse::parse(r#"dr1003({"result":0,"ret_code":4,"msga":""})"#).unwrap();
+        assert_eq!(
+            arrears.failure_reason(),
+            Some(LoginFailureReason::AccountArrears)
+        );
+
+        let isp_not_bound = DrcomLoginResponse::parse(
+            r#"dr1003({"result":0,"ret_code":1,"msga":"Rad:UserName_Err"})"#,
+        )
+        .unwrap();
+        assert_eq!(
+            isp_not_bound.failure_reason(),
+            Some(LoginFailureReason::IspNotBound)
+        );
+
+        // 只匹配完整的附加信息，提到关键字的其他文本保留原样
+        assert_eq!(
+            LoginFailureReason::classify(1, "请确认在线设备和密码后重试"),
+            LoginFailureReason::Unknown("请确认在线设备和密码后重试".to_string())
+        );
+        assert_eq!(
+            LoginFailureReason::classify(5, "account disabled by operator"),
+            LoginFailureReason::AccountSuspended
+        );
+
+        assert_eq!(DrcomLoginResponse::parse("<html>注销页</html>"), None);
+    }
+}
@@ -2,16 +2,19 @@
 //!
 //! 用于GUI和核心模块之间数据传输的结构体
 
-use crate::core::config::{AccountConfig, ConfigData, SettingsConfig};
+use crate::core::config::{AccountConfig, ConfigData};
+use crate::core::error::AppResult;
 use crate::core::normalize_isp;
+use crate::core::secret_store::secret_store_from_config;
+use crate::core::secret_string::SecretString;
 
 /// GUI配置数据传输对象
 #[derive(Clone, PartialEq, Default)]
 pub struct GuiConfigDto {
     /// 用户名
     pub username: String,
-    /// 密码（明文）
-    pub password: String,
+    /// 密码（明文），释放时清零
+    pub password: SecretString,
     /// 已加密的密码
     pub encrypted_password: String,
     /// 运营商
@@ -22,7 +25,7 @@ pub struct GuiConfigDto {
 
 impl GuiConfigDto {
     /// 创建新的GUI配置DTO
-    pub fn new(username: String, password: String, isp: String, auto_start: bool) -> Self {
+    pub fn new(username: String, password: SecretString, isp: String, auto_start: bool) -> Self {
         Self {
             username,
             password,
@@ -31,6 +34,27 @@ impl GuiConfigDto {
             auto_start,
         }
     }
+
+    /// 合并到现有配置中，得到要保存的`ConfigData`
+    ///
+    /// 输入了新密码时通过`[secret]`配置的密码存储保存，`config`后端按原有密码的加密方式加密，
+    /// `keyring`后端写入系统密钥环；否则保留已有的加密密码
+    pub fn to_config(&self) -> AppResult<ConfigData> {
+        let mut config = ConfigData::load_existing_or_default();
+        config.account = AccountConfig {
+            username: self.username.clone(),
+            encrypted_password: self.encrypted_password.clone(),
+            isp: normalize_isp(&self.isp),
+        };
+        config.settings.auto_start = self.auto_start;
+
+        if !self.password.is_empty() {
+            secret_store_from_config(&config.secret)
+                .set_password(&mut config.account, self.password.expose())?;
+        }
+
+        Ok(config)
+    }
 }
 
 impl From<ConfigData> for GuiConfigDto {
@@ -38,7 +62,7 @@ impl From<ConfigData> for GuiConfigDto {
     fn from(config: ConfigData) -> Self {
         Self {
             username: config.account.username,
-            password: String::new(),
+            password: SecretString::default(),
             encrypted_password: config.account.encrypted_password,
             isp: if config.account.isp.is_empty() {
                 "校园网".to_string()
@@ -49,33 +73,3 @@ impl From<ConfigData> for GuiConfigDto {
         }
     }
 }
-
-impl From<GuiConfigDto> for ConfigData {
-    /// 从`GuiConfigDto`转换为`ConfigData`
-    fn from(gui_config: GuiConfigDto) -> Self {
-        // 先加载现有配置
-        let existing_config = ConfigData::load_existing_or_default();
-
-        let encrypted_password = if !gui_config.password.is_empty() {
-            // 只有在提供了新密码时才进行加密
-            crate::core::crypto::generate_encrypted_password(&gui_config.password)
-        } else {
-            // 否则使用已有的加密密码
-            gui_config.encrypted_password.clone()
-        };
-
-        ConfigData {
-            network: existing_config.network,
-            account: AccountConfig {
-                username: gui_config.username.clone(),
-                encrypted_password,
-                isp: normalize_isp(&gui_config.isp),
-            },
-            logging: existing_config.logging,
-            settings: SettingsConfig {
-                auto_start: gui_config.auto_start,
-            },
-            message: existing_config.message,
-        }
-    }
-}
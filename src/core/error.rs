@@ -104,11 +104,7 @@ pub fn generate_user_friendly_message(error: &AppError) -> String {
                 format!("TLS连接错误: {}", msg)
             }
             NetworkError::HttpError(msg) | NetworkError::Other(msg) => {
-                if msg.contains("ldap auth error") || msg.contains("Msg=01") {
-                    "登录请求失败: 账号或密码错误".to_string()
-                } else {
-                    format!("登录请求失败: {}", msg)
-                }
+                format!("登录请求失败: {}", msg)
             }
         },
         AppError::PasswordDecryptionError { user_msg, .. } => user_msg.clone(),
@@ -20,6 +20,12 @@ pub enum AppEvent<'a> {
         message: &'a str,
         elapsed_time: f64,
     },
+    /// 注销完成
+    LogoutCompleted {
+        success: bool,
+        message: &'a str,
+        elapsed_time: f64,
+    },
     /// 配置加载完成
     ConfigLoaded { success: bool, message: &'a str },
     /// 配置保存完成
@@ -136,6 +142,20 @@ pub fn notify_login_attempted(
     });
 }
 
+/// 通知注销完成事件的通用函数
+pub fn notify_logout_completed(
+    event_bus: &EventBus,
+    success: bool,
+    message: &str,
+    elapsed_time: f64,
+) {
+    event_bus.dispatch(AppEvent::LogoutCompleted {
+        success,
+        message,
+        elapsed_time,
+    });
+}
+
 /// 通知配置加载事件的通用函数
 pub fn notify_config_loaded(event_bus: &EventBus, success: bool, message: &str) {
     event_bus.dispatch(AppEvent::ConfigLoaded { success, message });
@@ -1,13 +1,19 @@
 //! 流量信息查询模块
 //!
-//! 该模块负责登录到用户自助服务系统并获取剩余流量信息
+//! 该模块负责登录到用户自助服务系统并获取剩余流量、余额、套餐等账户信息
 //! 该模块访问的系统与登录校园网的系统不同，各自独立
 
+use crate::core::flow_history::{FlowForecast, FlowHistory};
+use crate::core::secret_string::SecretString;
+use chrono::Local;
 use regex::Regex;
 use reqwest::Client;
 use reqwest::cookie::Jar;
+use serde_json::Value;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use zeroize::Zeroize;
 
 /// 流量服务错误类型
 #[derive(Debug)]
@@ -15,6 +21,7 @@ pub enum FlowError {
     NetworkError(String),
     ParseError(String),
     LoginFailed(String),
+    OperationFailed(String),
 }
 
 impl std::fmt::Display for FlowError {
@@ -23,6 +30,7 @@ impl std::fmt::Display for FlowError {
             FlowError::NetworkError(msg) => write!(f, "网络错误: {}", msg),
             FlowError::ParseError(msg) => write!(f, "解析错误: {}", msg),
             FlowError::LoginFailed(msg) => write!(f, "登录失败: {}", msg),
+            FlowError::OperationFailed(msg) => write!(f, "操作失败: {}", msg),
         }
     }
 }
@@ -64,11 +72,174 @@ impl UserFlowInfo {
     }
 }
 
+/// 格式化流量大小，根据大小选择MB或GB
+pub fn format_flow_size(flow_mb: f64) -> String {
+    if flow_mb >= 1024.0 {
+        format!("{:.2}GB", flow_mb / 1024.0)
+    } else {
+        format!("{:.2}MB", flow_mb)
+    }
+}
+
+/// 自助服务系统会话在本地视为有效的时长，超过后重新登录
+const SESSION_TTL: Duration = Duration::from_secs(20 * 60);
+
+/// 仪表板页面路径
+const DASHBOARD_PATH: &str = "/dashboard";
+
+/// 仪表板页面内嵌JSON中的字段名
+const BALANCE_FIELD: &str = "leftMoney";
+const USED_FLOW_FIELD: &str = "useFlow";
+const USER_GROUP_ID_FIELD: &str = "userGroupId";
+const USER_GROUP_NAME_FIELD: &str = "userGroupName";
+const PACKAGE_NAME_FIELD: &str = "serviceName";
+const ONLINE_TIME_FIELD: &str = "useTime";
+
+/// 用户自助服务系统的账户信息
+///
+/// 除剩余流量外，其他字段在页面中缺失或无法解析时为`None`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelfServiceAccountInfo {
+    /// 剩余流量（MB）
+    pub left_flow: f64,
+    /// 已用流量（MB）
+    pub used_flow: Option<f64>,
+    /// 账户余额（元）
+    pub balance: Option<f64>,
+    /// 用户组编号，如13-16对应学生组大一到大四
+    pub user_group_id: Option<u32>,
+    /// 用户组名称，如`学生组-大一`
+    pub user_group_name: Option<String>,
+    /// 套餐名称
+    pub package_name: Option<String>,
+    /// 本月在线时长（分钟）
+    pub online_time_minutes: Option<f64>,
+}
+
+impl SelfServiceAccountInfo {
+    /// 剩余流量信息
+    pub fn flow_info(&self) -> UserFlowInfo {
+        UserFlowInfo::new(self.left_flow)
+    }
+
+    /// 格式化账户余额，如`余额12.50元`
+    pub fn format_balance(&self) -> String {
+        self.balance
+            .map(|balance| format!("余额{:.2}元", balance))
+            .unwrap_or_default()
+    }
+
+    /// 格式化套餐信息，没有套餐名称时使用用户组名称
+    pub fn format_package(&self) -> String {
+        self.package_name
+            .as_ref()
+            .or(self.user_group_name.as_ref())
+            .map(|name| format!("套餐{}", name))
+            .unwrap_or_default()
+    }
+}
+
+/// 在线设备（认证系统中的一个在线会话）
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OnlineDevice {
+    /// 会话编号，强制下线时使用
+    pub session_id: String,
+    /// IP地址
+    pub ip: String,
+    /// MAC地址
+    pub mac: String,
+    /// 登录时间
+    pub login_time: String,
+}
+
+impl OnlineDevice {
+    /// 转换为消息文本
+    pub fn to_message(&self) -> String {
+        format!("{} {} 登录于{}", self.ip, self.mac, self.login_time)
+    }
+
+    /// 是否与会话编号、IP或MAC地址匹配，MAC地址不区分大小写和分隔符
+    pub fn matches(&self, target: &str) -> bool {
+        let normalize_mac = |mac: &str| mac.replace([':', '-'], "").to_lowercase();
+        self.session_id == target
+            || self.ip == target
+            || (!self.mac.is_empty() && normalize_mac(&self.mac) == normalize_mac(target))
+    }
+}
+
+/// 解析在线设备列表，列表为JSON数组，也兼容`{"rows": [...]}`形式
+fn parse_online_devices(content: &str) -> Result<Vec<OnlineDevice>, FlowError> {
+    let value: Value = serde_json::from_str(content)
+        .map_err(|e| FlowError::ParseError(format!("无法解析在线设备列表: {}", e)))?;
+    let rows = value
+        .as_array()
+        .or_else(|| value.get("rows").and_then(Value::as_array))
+        .ok_or_else(|| FlowError::ParseError("在线设备列表格式错误".to_string()))?;
+
+    // 字段可能是字符串或数字
+    let field = |row: &Value, name: &str| match row.get(name) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+
+    Ok(rows
+        .iter()
+        .map(|row| OnlineDevice {
+            session_id: field(row, "sessionId"),
+            ip: field(row, "ip"),
+            mac: field(row, "mac"),
+            login_time: field(row, "loginTime"),
+        })
+        .filter(|device| !device.session_id.is_empty())
+        .collect())
+}
+
+/// 已登录的自助服务系统会话，登录凭据保存在HTTP客户端的cookie中
+struct SelfServiceSession {
+    /// 登录的账号
+    account: String,
+    /// 登录密码的MD5，密码修改后不再复用会话
+    ///
+    /// 自助服务系统直接使用MD5登录，与密码同样敏感，释放时清零
+    password_digest: SecretString,
+    /// 本地认为会话过期的时间
+    expires_at: Instant,
+}
+
+impl SelfServiceSession {
+    fn new(account: &str, password_digest: SecretString) -> Self {
+        Self {
+            account: account.to_string(),
+            password_digest,
+            expires_at: Instant::now() + SESSION_TTL,
+        }
+    }
+
+    /// 会话是否属于指定账号且未过期
+    fn is_valid_for(&self, account: &str, password_digest: &SecretString) -> bool {
+        self.account == account
+            && self.password_digest == *password_digest
+            && Instant::now() < self.expires_at
+    }
+}
+
+/// 页面是否为登录页（会话失效时会被重定向到登录页）
+fn is_login_page(url: &reqwest::Url, content: &str) -> bool {
+    url.path().contains("/login") || content.contains(r#"name="account""#)
+}
+
 /// 流量服务
+///
+/// 克隆的实例共享HTTP客户端和登录会话
 #[derive(Clone)]
 pub struct FlowService {
     client: Client,
     base_url: String,
+    /// 流量历史记录，设置后每次查询成功都会追加记录
+    history: Option<FlowHistory>,
+    /// 已登录的会话，查询期间持有锁，并发的查询会复用同一次登录
+    session: Arc<Mutex<Option<SelfServiceSession>>>,
 }
 
 impl FlowService {
@@ -94,12 +265,180 @@ impl FlowService {
 
         let base_url = "https://nicdrcom.guet.edu.cn/Self".to_string();
 
-        Self { client, base_url }
+        Self {
+            client,
+            base_url,
+            history: None,
+            session: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 指定用户自助服务系统地址，如`https://nicdrcom.guet.edu.cn/Self`
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// 指定流量历史记录文件
+    pub fn with_history(mut self, history: FlowHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// 根据流量历史计算指定账号的日均消耗和预计用完日期
+    ///
+    /// 未设置流量历史或记录不足时返回`None`
+    pub fn flow_forecast(&self, account: &str) -> Option<FlowForecast> {
+        self.history
+            .as_ref()?
+            .forecast(account, Local::now().naive_local())
+            .ok()
+            .flatten()
+    }
+
+    /// 登录用户自助服务系统并获取剩余流量
+    ///
+    /// 只需要剩余流量时使用，完整的账户信息见`get_account_info`
+    pub async fn get_user_flow_info(
+        &self,
+        account: &str,
+        password: &SecretString,
+    ) -> Result<UserFlowInfo, FlowError> {
+        self.get_account_info(account, password)
+            .await
+            .map(|info| info.flow_info())
+    }
+
+    /// 登录用户自助服务系统并获取账户信息
+    ///
+    /// 从仪表板页面提取剩余流量、余额、套餐等信息，设置了流量历史时追加一条记录；
+    /// 已有未过期的会话时直接读取仪表板页面，不再重新登录
+    ///
+    /// # 参数
+    /// * `account` - 用户账号
+    /// * `password` - 用户密码（明文）
+    ///
+    /// # 返回值
+    /// 成功时返回SelfServiceAccountInfo结构体，失败时返回FlowError错误
+    pub async fn get_account_info(
+        &self,
+        account: &str,
+        password: &SecretString,
+    ) -> Result<SelfServiceAccountInfo, FlowError> {
+        let dashboard_content = self
+            .get_with_session(account, password, DASHBOARD_PATH)
+            .await?;
+        let account_info = self.extract_account_info(&dashboard_content)?;
+
+        // 记录失败不影响查询结果
+        if let Some(history) = &self.history {
+            let _ = history.record(Local::now().naive_local(), account, account_info.left_flow);
+        }
+
+        Ok(account_info)
+    }
+
+    /// 登录用户自助服务系统并列出在线设备
+    pub async fn list_online_devices(
+        &self,
+        account: &str,
+        password: &SecretString,
+    ) -> Result<Vec<OnlineDevice>, FlowError> {
+        let content = self
+            .get_with_session(account, password, "/dashboard/getOnlineList")
+            .await?;
+
+        parse_online_devices(&content)
+    }
+
+    /// 登录用户自助服务系统并强制指定会话下线
+    ///
+    /// # 参数
+    /// * `session_id` - `list_online_devices`返回的会话编号
+    pub async fn kick_device(
+        &self,
+        account: &str,
+        password: &SecretString,
+        session_id: &str,
+    ) -> Result<(), FlowError> {
+        let path = format!(
+            "/dashboard/tooffline?sessionid={}",
+            urlencoding::encode(session_id)
+        );
+        let content = self.get_with_session(account, password, &path).await?;
+
+        let response: Value = serde_json::from_str(&content)
+            .map_err(|e| FlowError::ParseError(format!("无法解析下线结果: {}", e)))?;
+        if response.get("success").and_then(Value::as_bool) == Some(true) {
+            Ok(())
+        } else {
+            let message = response
+                .get("msg")
+                .and_then(Value::as_str)
+                .unwrap_or("下线失败");
+            Err(FlowError::OperationFailed(message.to_string()))
+        }
     }
 
-    /// 登录用户自助服务系统并获取流量信息
+    /// 使用已登录的会话请求页面，没有会话、会话过期或被重定向到登录页时重新登录
     ///
-    /// 该函数通过模拟用户登录流程来获取流量信息，具体步骤如下：
+    /// # 参数
+    /// * `path` - 相对于自助服务系统地址的路径，如`/dashboard`
+    async fn get_with_session(
+        &self,
+        account: &str,
+        password: &SecretString,
+        path: &str,
+    ) -> Result<String, FlowError> {
+        let mut session = self.session.lock().await;
+        let url = format!("{}{}", self.base_url, path);
+        let password_digest = self.md5_encrypt(password);
+
+        if session
+            .as_ref()
+            .is_some_and(|session| session.is_valid_for(account, &password_digest))
+            && let Some(content) = self.get_page(&url).await?
+        {
+            *session = Some(SelfServiceSession::new(account, password_digest));
+            return Ok(content);
+        }
+        *session = None;
+
+        let dashboard_content = self.login_self_service(account, password).await?;
+        *session = Some(SelfServiceSession::new(account, password_digest));
+
+        if path == DASHBOARD_PATH {
+            return Ok(dashboard_content);
+        }
+
+        match self.get_page(&url).await? {
+            Some(content) => Ok(content),
+            None => {
+                *session = None;
+                Err(FlowError::LoginFailed("登录后会话仍然无效".to_string()))
+            }
+        }
+    }
+
+    /// 请求页面，被重定向到登录页时返回`None`
+    async fn get_page(&self, url: &str) -> Result<Option<String>, FlowError> {
+        let response = self
+            .create_request_builder(url)
+            .send()
+            .await
+            .map_err(|e| FlowError::NetworkError(format!("请求{}失败: {}", url, e)))?;
+        let final_url = response.url().clone();
+        let content = response
+            .text()
+            .await
+            .map_err(|e| FlowError::NetworkError(format!("读取{}失败: {}", url, e)))?;
+
+        Ok((!is_login_page(&final_url, &content)).then_some(content))
+    }
+
+    /// 登录用户自助服务系统，返回仪表板页面内容
+    ///
+    /// 该函数通过模拟用户登录流程登录，具体步骤如下：
     /// 1. 获取登录页面以提取checkcode（验证码标识）
     /// 2. 访问验证码图片URL（不需要保存图片，只需访问）
     ///     > 非常奇怪的机制，图片验证码只在第三级的流量充值页面出现
@@ -110,19 +449,11 @@ impl FlowService {
     /// 3. 生成随机四位数字验证码
     /// 4. 对密码进行MD5加密
     /// 5. 构造登录数据并执行登录
-    /// 6. 从仪表板页面提取剩余流量信息
-    ///
-    /// # 参数
-    /// * `account` - 用户账号
-    /// * `password` - 用户密码（明文）
-    ///
-    /// # 返回值
-    /// 成功时返回包含用户流量信息的UserFlowInfo结构体，失败时返回FlowError错误
-    pub async fn get_user_flow_info(
+    async fn login_self_service(
         &self,
         account: &str,
-        password: &str,
-    ) -> Result<UserFlowInfo, FlowError> {
+        password: &SecretString,
+    ) -> Result<String, FlowError> {
         // 1. 获取登录页面以提取checkcode
         let login_page = self.get_login_page().await?;
 
@@ -142,19 +473,12 @@ impl FlowService {
         // 5. 构造登录数据并执行登录
         let login_data = [
             ("account", account),
-            ("password", &encrypted_password),
+            ("password", encrypted_password.expose()),
             ("checkcode", &checkcode),
             ("code", &random_code),
         ];
 
-        let dashboard_content = self.login(&login_data).await?;
-
-        // 6. 从仪表板页面提取剩余流量信息
-        let left_flow = self
-            .extract_remaining_flow_from_user_info(&dashboard_content)
-            .ok_or_else(|| FlowError::ParseError("无法提取剩余流量信息".to_string()))?;
-
-        Ok(UserFlowInfo::new(left_flow))
+        self.login(&login_data).await
     }
 
     /// 创建带有通用请求头的GET请求构建器
@@ -214,10 +538,12 @@ impl FlowService {
         format!("{:04}", rng.random_range(0..=9999))
     }
 
-    /// MD5加密
-    fn md5_encrypt(&self, text: &str) -> String {
-        let digest = md5::compute(text.as_bytes());
-        format!("{:x}", digest)
+    /// 计算密码的MD5，自助服务系统使用它登录，因此同样以`SecretString`保存
+    fn md5_encrypt(&self, password: &SecretString) -> SecretString {
+        let mut digest = md5::compute(password.expose().as_bytes());
+        let hex = SecretString::new(format!("{:x}", digest));
+        digest.0.zeroize();
+        hex
     }
 
     /// 执行登录操作
@@ -324,6 +650,40 @@ impl FlowService {
         // 返回div方式的结果
         div_result
     }
+
+    /// 从仪表板页面提取账户信息，剩余流量缺失时返回错误
+    fn extract_account_info(
+        &self,
+        dashboard_content: &str,
+    ) -> Result<SelfServiceAccountInfo, FlowError> {
+        let left_flow = self
+            .extract_remaining_flow_from_user_info(dashboard_content)
+            .ok_or_else(|| FlowError::ParseError("无法提取剩余流量信息".to_string()))?;
+
+        let field = |name| self.extract_json_field(dashboard_content, name);
+
+        Ok(SelfServiceAccountInfo {
+            left_flow,
+            used_flow: field(USED_FLOW_FIELD).and_then(|v| v.parse().ok()),
+            balance: field(BALANCE_FIELD).and_then(|v| v.parse().ok()),
+            user_group_id: field(USER_GROUP_ID_FIELD).and_then(|v| v.parse().ok()),
+            user_group_name: field(USER_GROUP_NAME_FIELD),
+            package_name: field(PACKAGE_NAME_FIELD),
+            online_time_minutes: field(ONLINE_TIME_FIELD).and_then(|v| v.parse().ok()),
+        })
+    }
+
+    /// 从页面内嵌的JSON中提取字段值，忽略空字符串和null
+    fn extract_json_field(&self, user_info_text: &str, name: &str) -> Option<String> {
+        let pattern = format!(
+            r#""{}"\s*:\s*(?:"([^"]*)"|([^,}}\s]+))"#,
+            regex::escape(name)
+        );
+        let captures = Regex::new(&pattern).ok()?.captures(user_info_text)?;
+        let value = captures.get(1).or_else(|| captures.get(2))?.as_str().trim();
+
+        (!value.is_empty() && value != "null").then(|| value.to_string())
+    }
 }
 
 impl Default for FlowService {
@@ -336,25 +696,36 @@ impl Default for FlowService {
 mod tests {
     use super::*;
 
-    #[tokio::test]
-    async fn test_get_user_flow_info() {
-        // 测试时需要替换为实际的账号和密码
-        // 后续如果校方把接口变动，请自行修改。但估计短时间内是不会改的
-        // "userGroupId":13,"userGroupName":"学生组-大一"
-        // "userGroupId":14,"userGroupName":"学生组-大二"
-        // "userGroupId":15,"userGroupName":"学生组-大三"
-        // "userGroupId":16,"userGroupName":"学生组-大四"
-        let account = "xxx";
-        let password = "xxx";
-
-        let flow_service = FlowService::new();
-        match flow_service.get_user_flow_info(account, password).await {
-            Ok(flow_info) => {
-                println!("剩余流量：{:?} MB", flow_info)
+    #[test]
+    fn test_extract_account_info() {
+        let dashboard = r#"<dl><dt>2048.00 <small>M</small></dt><dd>剩余流量</dd></dl><script>var user = {"leftFlow":2050.00,"useFlow":8192.5,"leftMoney":12.5,"userGroupId":14,"userGroupName":"学生组-大二","serviceName":null,"useTime":"360"};</script>"#;
+
+        let info = FlowService::new().extract_account_info(dashboard).unwrap();
+        assert_eq!(
+            info,
+            SelfServiceAccountInfo {
+                left_flow: 2048.0,
+                used_flow: Some(8192.5),
+                balance: Some(12.5),
+                user_group_id: Some(14),
+                user_group_name: Some("学生组-大二".to_string()),
+                package_name: None,
+                online_time_minutes: Some(360.0),
             }
-            Err(e) => {
-                println!("获取流量信息失败：{}", e);
-            }
-        }
+        );
+        assert_eq!(info.format_balance(), "余额12.50元");
+        assert_eq!(info.format_package(), "套餐学生组-大二");
+    }
+
+    #[test]
+    fn test_parse_online_devices() {
+        let content = r#"[{"sessionId":"1024","ip":"10.33.1.2","mac":"AA-BB-CC-DD-EE-FF","loginTime":"2025-10-01 08:00:00"},{"sessionId":null}]"#;
+
+        let devices = parse_online_devices(content).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].ip, "10.33.1.2");
+        assert!(devices[0].matches("aa:bb:cc:dd:ee:ff"));
+        assert!(devices[0].matches("1024"));
+        assert!(!devices[0].matches("10.33.1.3"));
     }
 }
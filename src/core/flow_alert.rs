@@ -0,0 +1,188 @@
+//! 流量提醒模块
+//!
+//! 剩余流量低于`[flow_alert]`中配置的阈值时发送通知，
+//! 每个阈值在一个计费周期内只提醒一次，已提醒的阈值保存在状态文件中，重启后不会重复提醒
+
+use crate::core::error::{AppError, AppResult};
+use crate::core::flow::format_flow_size;
+use chrono::{Datelike, Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// 状态文件名
+pub const FLOW_ALERT_STATE_FILE_NAME: &str = "flow_alert_state.toml";
+
+/// 流量提醒配置
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FlowAlertConfig {
+    /// 是否启用（仅校园网运营商）
+    pub enabled: bool,
+    /// 提醒阈值（MB）
+    pub thresholds_mb: Vec<f64>,
+    /// 计费周期开始的日期（每月几号，1-31），当月没有这一天时从当月最后一天开始
+    pub billing_day: u32,
+}
+
+impl Default for FlowAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            thresholds_mb: vec![5120.0, 1024.0, 200.0],
+            billing_day: 1,
+        }
+    }
+}
+
+impl FlowAlertConfig {
+    /// 指定日期所在计费周期的标识，如`2025-09`
+    pub fn billing_cycle(&self, date: NaiveDate) -> String {
+        let start = if date.day() >= self.billing_day_in_month(date) {
+            date
+        } else {
+            date.checked_sub_months(Months::new(1)).unwrap_or(date)
+        };
+        format!("{:04}-{:02}", start.year(), start.month())
+    }
+
+    /// 指定日期所在月份的计费周期开始日，如`billing_day = 31`在2月为28或29号
+    fn billing_day_in_month(&self, date: NaiveDate) -> u32 {
+        let days_in_month = date
+            .with_day(1)
+            .and_then(|first| first.checked_add_months(Months::new(1)))
+            .and_then(|next| next.pred_opt())
+            .map(|last| last.day())
+            .unwrap_or(28);
+        self.billing_day.clamp(1, days_in_month)
+    }
+}
+
+/// 已提醒的阈值
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct FlowAlertState {
+    /// 计费周期标识
+    pub cycle: String,
+    /// 本周期内已提醒的阈值（MB）
+    pub fired_mb: Vec<f64>,
+}
+
+impl FlowAlertState {
+    /// 读取状态文件，文件不存在或无法解析时返回空状态
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// 写入状态文件
+    pub fn save(&self, path: &Path) -> AppResult<()> {
+        let content = toml::to_string(self)
+            .map_err(|e| AppError::ConfigError(format!("无法序列化流量提醒状态: {}", e)))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::ConfigError(format!("无法创建状态目录: {}", e)))?;
+        }
+
+        fs::write(path, content)
+            .map_err(|e| AppError::ConfigError(format!("无法写入流量提醒状态: {}", e)))
+    }
+
+    /// 根据剩余流量（MB）更新状态，返回本次新跨过的阈值，按从大到小排列
+    ///
+    /// 进入新的计费周期时清空已提醒的阈值；剩余流量回到阈值以上（如充值）时，
+    /// 该阈值可以再次提醒
+    pub fn update(&mut self, config: &FlowAlertConfig, cycle: &str, left_flow: f64) -> Vec<f64> {
+        if self.cycle != cycle {
+            self.cycle = cycle.to_string();
+            self.fired_mb.clear();
+        }
+
+        self.fired_mb.retain(|threshold| left_flow < *threshold);
+
+        let mut crossed: Vec<f64> = config
+            .thresholds_mb
+            .iter()
+            .copied()
+            .filter(|threshold| left_flow < *threshold && !self.fired_mb.contains(threshold))
+            .collect();
+        crossed.sort_by(|a, b| b.total_cmp(a));
+
+        self.fired_mb.extend(&crossed);
+        crossed
+    }
+}
+
+/// 检查剩余流量并更新状态文件，返回需要发送的提醒消息
+///
+/// 同时跨过多个阈值时只提醒最低的一个
+pub fn check_flow_alert(
+    config: &FlowAlertConfig,
+    state_path: &Path,
+    left_flow: f64,
+) -> AppResult<Option<String>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let mut state = FlowAlertState::load(state_path);
+    let cycle = config.billing_cycle(Local::now().date_naive());
+    let crossed = state.update(config, &cycle, left_flow);
+    if crossed.is_empty() {
+        return Ok(None);
+    }
+    state.save(state_path)?;
+
+    Ok(crossed.last().map(|threshold| {
+        format!(
+            "剩余流量已低于{}，当前剩余{}",
+            format_flow_size(*threshold),
+            format_flow_size(left_flow)
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flow_alert_fires_once_per_threshold_per_cycle() {
+        let config = FlowAlertConfig {
+            enabled: true,
+            billing_day: 15,
+            ..Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        assert_eq!(config.billing_cycle(date), "2024-12");
+
+        let mut state = FlowAlertState::default();
+        assert_eq!(state.update(&config, "2024-12", 6000.0), Vec::<f64>::new());
+        assert_eq!(
+            state.update(&config, "2024-12", 900.0),
+            vec![5120.0, 1024.0]
+        );
+        assert_eq!(state.update(&config, "2024-12", 800.0), Vec::<f64>::new());
+        assert_eq!(state.update(&config, "2024-12", 100.0), vec![200.0]);
+
+        // 新的计费周期重新提醒
+        assert_eq!(state.update(&config, "2025-01", 4000.0), vec![5120.0]);
+
+        // 当月没有计费日时从最后一天开始
+        let config = FlowAlertConfig {
+            billing_day: 31,
+            ..Default::default()
+        };
+        let cycle = |y, m, d| config.billing_cycle(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+        assert_eq!(cycle(2025, 2, 27), "2025-01");
+        assert_eq!(cycle(2025, 2, 28), "2025-02");
+        assert_eq!(cycle(2024, 2, 28), "2024-01");
+        assert_eq!(cycle(2024, 2, 29), "2024-02");
+        assert_eq!(cycle(2025, 4, 30), "2025-04");
+        assert_eq!(cycle(2025, 5, 30), "2025-04");
+        assert_eq!(cycle(2025, 5, 31), "2025-05");
+    }
+}
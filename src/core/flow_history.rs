@@ -0,0 +1,255 @@
+//! 流量历史模块
+//!
+//! 每次查询到剩余流量后追加一条带时间的记录到`flow_history.csv`，
+//! 根据最近的记录计算日均消耗和预计用完的日期
+//!
+//! 记录文件与流量提醒状态放在同一目录，每行格式为`时间,账号,剩余流量（MB）`，
+//! 写入时删除最近7天以外的记录
+
+use crate::core::error::{AppError, AppResult};
+use crate::core::flow::format_flow_size;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 记录文件名
+pub const FLOW_HISTORY_FILE_NAME: &str = "flow_history.csv";
+
+/// 记录文件表头
+const CSV_HEADER: &str = "time,username,left_flow_mb";
+
+/// 时间格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 计算消耗速度使用的记录范围（天）
+const FORECAST_WINDOW_DAYS: i64 = 7;
+
+/// 计算消耗速度所需的最短记录跨度（秒）
+const MIN_FORECAST_SPAN_SECS: i64 = 3600;
+
+/// 记录文件最多保留的记录条数，频繁查询时只保留最新的记录
+const MAX_SAMPLES: usize = 2000;
+
+/// 流量记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowSample {
+    /// 记录时间
+    pub time: NaiveDateTime,
+    /// 账号
+    pub username: String,
+    /// 剩余流量（MB）
+    pub left_flow: f64,
+}
+
+/// 流量消耗预测
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowForecast {
+    /// 日均消耗（MB）
+    pub daily_usage_mb: f64,
+    /// 最近一次记录的剩余流量（MB）
+    pub left_flow: f64,
+    /// 按当前速度预计用完的日期，没有消耗时为`None`
+    pub exhaustion_date: Option<NaiveDate>,
+}
+
+impl FlowForecast {
+    /// 根据按时间排列的记录计算消耗预测
+    ///
+    /// 只使用最近`FORECAST_WINDOW_DAYS`天内、最后一次流量回升（计费周期重置或充值）之后的记录，
+    /// 记录跨度不足一小时时返回`None`
+    pub fn from_samples(samples: &[FlowSample], now: NaiveDateTime) -> Option<Self> {
+        let window_start = now - Duration::days(FORECAST_WINDOW_DAYS);
+        let recent: Vec<&FlowSample> = samples
+            .iter()
+            .filter(|sample| sample.time >= window_start)
+            .collect();
+
+        let last = *recent.last()?;
+        let first_index = recent
+            .windows(2)
+            .rposition(|pair| pair[1].left_flow > pair[0].left_flow)
+            .map_or(0, |index| index + 1);
+        let first = recent[first_index];
+
+        let span_secs = (last.time - first.time).num_seconds();
+        if span_secs < MIN_FORECAST_SPAN_SECS {
+            return None;
+        }
+
+        let daily_usage_mb = (first.left_flow - last.left_flow) / span_secs as f64 * 86400.0;
+        let exhaustion_date = (daily_usage_mb > 0.0).then(|| {
+            let secs_left = last.left_flow / daily_usage_mb * 86400.0;
+            (last.time + Duration::seconds(secs_left as i64)).date()
+        });
+
+        Some(Self {
+            daily_usage_mb,
+            left_flow: last.left_flow,
+            exhaustion_date,
+        })
+    }
+
+    /// 转换为消息文本
+    pub fn to_message(&self) -> String {
+        match self.exhaustion_date {
+            Some(date) if self.left_flow > 0.0 => format!(
+                "日均消耗{}，预计{}用完",
+                format_flow_size(self.daily_usage_mb),
+                date.format("%m月%d日")
+            ),
+            Some(_) => format!("日均消耗{}", format_flow_size(self.daily_usage_mb)),
+            None => "近期未消耗流量".to_string(),
+        }
+    }
+}
+
+/// 流量历史记录文件
+#[derive(Debug, Clone)]
+pub struct FlowHistory {
+    path: PathBuf,
+}
+
+impl FlowHistory {
+    /// 使用指定的记录文件
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 记录文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录，同时删除计算范围以外的旧记录，文件最多保留`MAX_SAMPLES`条记录
+    pub fn record(&self, time: NaiveDateTime, username: &str, left_flow: f64) -> AppResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::SystemError(format!("无法创建流量记录目录: {}", e)))?;
+        }
+
+        let existing = if self.path.exists() {
+            fs::read_to_string(&self.path)
+                .map_err(|e| AppError::SystemError(format!("无法读取流量记录文件: {}", e)))?
+        } else {
+            String::new()
+        };
+
+        let window_start = time - Duration::days(FORECAST_WINDOW_DAYS);
+        let mut lines: Vec<&str> = existing
+            .lines()
+            .filter(|line| parse_sample(line).is_some_and(|sample| sample.time >= window_start))
+            .collect();
+        let new_line = format!("{},{},{:.2}", time.format(TIME_FORMAT), username, left_flow);
+        lines.push(&new_line);
+        let skip = lines.len().saturating_sub(MAX_SAMPLES);
+
+        let mut content = String::from(CSV_HEADER);
+        content.push('\n');
+        for line in &lines[skip..] {
+            content.push_str(line);
+            content.push('\n');
+        }
+
+        // 先写入临时文件再替换，避免写入中断时丢失记录
+        let temp_path = self.path.with_extension("csv.tmp");
+        fs::write(&temp_path, content)
+            .map_err(|e| AppError::SystemError(format!("无法写入流量记录文件: {}", e)))?;
+        fs::rename(&temp_path, &self.path)
+            .map_err(|e| AppError::SystemError(format!("无法替换流量记录文件: {}", e)))
+    }
+
+    /// 读取指定账号的记录，跳过无法解析的行
+    pub fn load(&self, username: &str) -> AppResult<Vec<FlowSample>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| AppError::SystemError(format!("无法读取流量记录文件: {}", e)))?;
+
+        let mut samples: Vec<FlowSample> = content
+            .lines()
+            .filter_map(parse_sample)
+            .filter(|sample| sample.username == username)
+            .collect();
+        samples.sort_by_key(|sample| sample.time);
+
+        Ok(samples)
+    }
+
+    /// 根据指定账号的记录计算消耗预测
+    pub fn forecast(&self, username: &str, now: NaiveDateTime) -> AppResult<Option<FlowForecast>> {
+        Ok(FlowForecast::from_samples(&self.load(username)?, now))
+    }
+}
+
+/// 解析一行记录
+fn parse_sample(line: &str) -> Option<FlowSample> {
+    let mut fields = line.splitn(3, ',');
+    let time = NaiveDateTime::parse_from_str(fields.next()?, TIME_FORMAT).ok()?;
+    let username = fields.next()?.to_string();
+    let left_flow = fields.next()?.trim().parse().ok()?;
+
+    Some(FlowSample {
+        time,
+        username,
+        left_flow,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast_uses_samples_since_last_reset() {
+        let sample = |time: &str, left_flow: f64| FlowSample {
+            time: NaiveDateTime::parse_from_str(time, TIME_FORMAT).unwrap(),
+            username: "2100000000".to_string(),
+            left_flow,
+        };
+        let samples = [
+            sample("2025-09-28 08:00:00", 200.0),
+            sample("2025-10-01 08:00:00", 10240.0),
+            sample("2025-10-03 08:00:00", 8192.0),
+        ];
+        let now = NaiveDateTime::parse_from_str("2025-10-03 09:00:00", TIME_FORMAT).unwrap();
+
+        let forecast = FlowForecast::from_samples(&samples, now).unwrap();
+        assert_eq!(forecast.daily_usage_mb, 1024.0);
+        assert_eq!(
+            forecast.exhaustion_date,
+            NaiveDate::from_ymd_opt(2025, 10, 11)
+        );
+        assert_eq!(forecast.to_message(), "日均消耗1.00GB，预计10月11日用完");
+
+        assert_eq!(parse_sample("time,username,left_flow_mb"), None);
+        assert!(FlowForecast::from_samples(&samples[..1], now).is_none());
+    }
+
+    #[test]
+    fn test_record_prunes_samples_outside_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = FlowHistory::new(dir.path().join(FLOW_HISTORY_FILE_NAME));
+        let time = |time: &str| NaiveDateTime::parse_from_str(time, TIME_FORMAT).unwrap();
+
+        history
+            .record(time("2025-09-20 08:00:00"), "2100000000", 10240.0)
+            .unwrap();
+        history
+            .record(time("2025-09-28 08:00:00"), "2100000000", 9216.0)
+            .unwrap();
+        history
+            .record(time("2025-09-28 09:00:00"), "2100000001", 512.0)
+            .unwrap();
+
+        let samples = history.load("2100000000").unwrap();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].left_flow, 9216.0);
+        assert_eq!(history.load("2100000001").unwrap().len(), 1);
+
+        let content = fs::read_to_string(history.path()).unwrap();
+        assert!(content.starts_with(CSV_HEADER));
+        assert_eq!(content.lines().count(), 3);
+    }
+}
@@ -0,0 +1,84 @@
+//! 流量切换模块
+//!
+//! 校园网账号的剩余流量低于阈值时，注销校园网账号并改用运营商账号登录；
+//! 计费周期重置后校园网账号的剩余流量恢复，再切换回校园网账号
+//!
+//! 两个账号都取自`[profiles]`中的账号档案，切换时更新默认档案
+
+use serde::{Deserialize, Serialize};
+
+/// 流量切换配置
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FlowSwitchConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 切换阈值（MB），校园网账号剩余流量低于该值时切换到运营商账号
+    pub threshold_mb: f64,
+    /// 校园网账号档案名称
+    pub campus_profile: String,
+    /// 运营商账号档案名称
+    pub isp_profile: String,
+    /// 守护模式下查询剩余流量的间隔（秒）
+    pub check_interval_secs: u64,
+}
+
+impl Default for FlowSwitchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_mb: 512.0,
+            campus_profile: String::new(),
+            isp_profile: String::new(),
+            check_interval_secs: 1800,
+        }
+    }
+}
+
+/// 流量切换决策
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowSwitchDecision {
+    /// 保持当前账号
+    Stay,
+    /// 校园网流量不足，切换到运营商账号
+    SwitchToIsp,
+    /// 校园网流量已恢复，切换回校园网账号
+    SwitchToCampus,
+}
+
+impl FlowSwitchDecision {
+    /// 转换为消息文本
+    pub fn to_message(&self) -> &'static str {
+        match self {
+            FlowSwitchDecision::Stay => "无需切换账号",
+            FlowSwitchDecision::SwitchToIsp => "校园网流量不足，切换到运营商账号",
+            FlowSwitchDecision::SwitchToCampus => "校园网流量已恢复，切换回校园网账号",
+        }
+    }
+}
+
+impl FlowSwitchConfig {
+    /// 根据当前默认档案和校园网账号的剩余流量（MB）决定是否切换
+    pub fn decide(&self, active_profile: &str, left_flow: f64) -> FlowSwitchDecision {
+        if !self.enabled {
+            return FlowSwitchDecision::Stay;
+        }
+
+        if active_profile == self.campus_profile && left_flow < self.threshold_mb {
+            FlowSwitchDecision::SwitchToIsp
+        } else if active_profile == self.isp_profile && left_flow >= self.threshold_mb {
+            FlowSwitchDecision::SwitchToCampus
+        } else {
+            FlowSwitchDecision::Stay
+        }
+    }
+
+    /// 切换目标的档案名称
+    pub fn target_profile(&self, decision: FlowSwitchDecision) -> Option<&str> {
+        match decision {
+            FlowSwitchDecision::Stay => None,
+            FlowSwitchDecision::SwitchToIsp => Some(&self.isp_profile),
+            FlowSwitchDecision::SwitchToCampus => Some(&self.campus_profile),
+        }
+    }
+}
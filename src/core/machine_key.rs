@@ -0,0 +1,193 @@
+//! 机器标识模块
+//!
+//! 读取与当前机器绑定的唯一标识，用于派生加密配置密码的密钥
+//!
+//! 默认按以下顺序尝试，使用第一个成功读取的标识：
+//! 1. Windows：注册表 `HKLM\SOFTWARE\Microsoft\Cryptography\MachineGuid`
+//! 2. macOS：`ioreg` 输出的 `IOPlatformUUID`
+//! 3. Linux 等：`/etc/machine-id`
+//! 4. Linux 等：`/var/lib/dbus/machine-id`（旧版系统或未使用systemd的发行版）
+//!
+//! 全部失败时返回 `AppError::CryptoError`，不会退化为固定密钥
+
+use crate::core::error::{AppError, AppResult};
+use std::fs;
+use std::path::PathBuf;
+
+/// 机器标识提供者
+pub trait MachineKeyProvider: Send + Sync {
+    /// 提供者名称，用于错误信息
+    fn name(&self) -> String;
+
+    /// 读取机器唯一标识
+    fn machine_id(&self) -> AppResult<String>;
+}
+
+/// Windows 注册表中的 MachineGuid
+#[cfg(windows)]
+pub struct WindowsMachineGuidProvider;
+
+#[cfg(windows)]
+impl MachineKeyProvider for WindowsMachineGuidProvider {
+    fn name(&self) -> String {
+        "MachineGuid".to_string()
+    }
+
+    fn machine_id(&self) -> AppResult<String> {
+        use winreg::RegKey;
+        use winreg::enums::*;
+
+        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+        let key = hklm
+            .open_subkey("SOFTWARE\\Microsoft\\Cryptography")
+            .map_err(|e| AppError::CryptoError(format!("无法打开注册表项: {}", e)))?;
+        let machine_guid: String = key
+            .get_value("MachineGuid")
+            .map_err(|e| AppError::CryptoError(format!("无法获取MachineGuid值: {}", e)))?;
+        non_empty(machine_guid, "MachineGuid")
+    }
+}
+
+/// systemd/dbus 维护的 machine-id 文件
+pub struct MachineIdFileProvider {
+    path: PathBuf,
+}
+
+impl MachineIdFileProvider {
+    /// 创建读取指定文件的提供者
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl MachineKeyProvider for MachineIdFileProvider {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn machine_id(&self) -> AppResult<String> {
+        let content = fs::read_to_string(&self.path).map_err(|e| {
+            AppError::CryptoError(format!("无法读取{}: {}", self.path.display(), e))
+        })?;
+        non_empty(content, &self.name())
+    }
+}
+
+/// macOS 的 IOPlatformUUID
+#[cfg(target_os = "macos")]
+pub struct IoPlatformUuidProvider;
+
+#[cfg(target_os = "macos")]
+impl MachineKeyProvider for IoPlatformUuidProvider {
+    fn name(&self) -> String {
+        "IOPlatformUUID".to_string()
+    }
+
+    fn machine_id(&self) -> AppResult<String> {
+        let output = std::process::Command::new("ioreg")
+            .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+            .output()
+            .map_err(|e| AppError::CryptoError(format!("无法执行ioreg: {}", e)))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let uuid = stdout
+            .lines()
+            .find(|line| line.contains("\"IOPlatformUUID\""))
+            .and_then(|line| line.split('=').nth(1))
+            .map(|value| value.trim().trim_matches('"').to_string())
+            .unwrap_or_default();
+        non_empty(uuid, "IOPlatformUUID")
+    }
+}
+
+/// 去除首尾空白并拒绝空标识
+fn non_empty(value: String, source: &str) -> AppResult<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(AppError::CryptoError(format!("{}为空", source)));
+    }
+    Ok(value.to_string())
+}
+
+/// 当前平台的默认提供者，顺序即回退顺序
+pub fn default_providers() -> Vec<Box<dyn MachineKeyProvider>> {
+    let mut providers: Vec<Box<dyn MachineKeyProvider>> = Vec::new();
+
+    #[cfg(windows)]
+    providers.push(Box::new(WindowsMachineGuidProvider));
+
+    #[cfg(target_os = "macos")]
+    providers.push(Box::new(IoPlatformUuidProvider));
+
+    #[cfg(not(windows))]
+    {
+        providers.push(Box::new(MachineIdFileProvider::new("/etc/machine-id")));
+        providers.push(Box::new(MachineIdFileProvider::new(
+            "/var/lib/dbus/machine-id",
+        )));
+    }
+
+    providers
+}
+
+/// 依次尝试各提供者，返回第一个成功读取的机器标识
+///
+/// 全部失败时返回包含每个提供者失败原因的`CryptoError`
+pub fn resolve_machine_id(providers: &[Box<dyn MachineKeyProvider>]) -> AppResult<String> {
+    let mut failures = Vec::new();
+
+    for provider in providers {
+        match provider.machine_id() {
+            Ok(machine_id) => return Ok(machine_id),
+            Err(e) => failures.push(format!("{}: {}", provider.name(), e)),
+        }
+    }
+
+    if failures.is_empty() {
+        return Err(AppError::CryptoError(
+            "当前平台没有可用的机器标识来源".to_string(),
+        ));
+    }
+
+    Err(AppError::CryptoError(format!(
+        "无法获取机器标识（{}）",
+        failures.join("；")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeProvider(Option<&'static str>);
+
+    impl MachineKeyProvider for FakeProvider {
+        fn name(&self) -> String {
+            "fake".to_string()
+        }
+
+        fn machine_id(&self) -> AppResult<String> {
+            match self.0 {
+                Some(id) => non_empty(id.to_string(), "fake"),
+                None => Err(AppError::CryptoError("unavailable".to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_machine_id_fallback_order() {
+        let providers: Vec<Box<dyn MachineKeyProvider>> = vec![
+            Box::new(FakeProvider(None)),
+            Box::new(FakeProvider(Some("  \n"))),
+            Box::new(FakeProvider(Some("abc123\n"))),
+            Box::new(FakeProvider(Some("ignored"))),
+        ];
+        assert_eq!(resolve_machine_id(&providers).unwrap(), "abc123");
+
+        let providers: Vec<Box<dyn MachineKeyProvider>> = vec![Box::new(FakeProvider(None))];
+        assert!(matches!(
+            resolve_machine_id(&providers),
+            Err(AppError::CryptoError(_))
+        ));
+    }
+}
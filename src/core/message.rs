@@ -2,15 +2,21 @@
 //!
 //! 集中管理所有消息的生成、日志记录和通知显示
 
-use crate::core::config::{ConfigData, MessageConfig};
+use crate::core::config::{AccountConfig, ConfigData, MessageConfig, isp_display_name};
+use crate::core::drcom::LoginFailureReason;
 use crate::core::error::{AppError, AppResult};
 use crate::core::events::EventBus;
+use crate::core::flow::SelfServiceAccountInfo;
+use crate::core::flow_alert::{FLOW_ALERT_STATE_FILE_NAME, check_flow_alert};
+use crate::core::flow_history::{FLOW_HISTORY_FILE_NAME, FlowForecast, FlowHistory};
+use crate::core::network::local_ip;
+use crate::core::paths::state_file_path;
 use crate::core::service::LoginResult;
+use crate::core::template::{MessageTemplate, TemplateValues};
 use chrono::{Duration, Local, NaiveDateTime, TimeZone};
 use notify_rust::Notification;
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufRead, BufReader, Write};
-use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 /// 校园网状态枚举
@@ -73,7 +79,13 @@ pub struct MessageBuilder {
     campus_status: Option<CampusNetworkStatus>,
     wan_status: Option<WanStatus>,
     elapsed_time: Option<f64>,
-    flow_info: Option<String>,
+    left_flow: Option<f64>,
+    flow_forecast: Option<String>,
+    balance: Option<f64>,
+    package: Option<String>,
+    failure_reason: Option<LoginFailureReason>,
+    username: String,
+    ip: Option<String>,
     isp: String,
 }
 
@@ -84,7 +96,13 @@ impl MessageBuilder {
             campus_status: None,
             wan_status: None,
             elapsed_time: None,
-            flow_info: None,
+            left_flow: None,
+            flow_forecast: None,
+            balance: None,
+            package: None,
+            failure_reason: None,
+            username: String::new(),
+            ip: None,
             isp,
         }
     }
@@ -109,31 +127,54 @@ impl MessageBuilder {
 
     /// 设置流量信息（MB）
     pub fn with_flow_info(mut self, flow_mb: f64) -> Self {
-        self.flow_info = Some(Self::format_flow_info(flow_mb));
+        self.left_flow = Some(flow_mb);
         self
     }
 
-    /// 格式化流量信息
-    fn format_flow_info(flow_mb: f64) -> String {
-        if flow_mb == 0.0 {
-            "流量耗尽，限速不限量生效".to_string()
-        } else if flow_mb >= 1024.0 {
-            format!("剩余流量{:.2}GB", flow_mb / 1024.0)
-        } else {
-            format!("剩余流量{:.2}MB", flow_mb)
-        }
+    /// 设置自助服务系统的账户信息（剩余流量、余额和套餐）
+    pub fn with_account_info(mut self, info: &SelfServiceAccountInfo) -> Self {
+        self.balance = info.balance;
+        self.package = Some(info.format_package());
+        self.with_flow_info(info.left_flow)
+    }
+
+    /// 设置流量消耗预测
+    pub fn with_flow_forecast(mut self, forecast: &FlowForecast) -> Self {
+        self.flow_forecast = Some(forecast.to_message());
+        self
+    }
+
+    /// 设置登录失败原因，设置后校园网状态消息会附带具体原因
+    pub fn with_failure_reason(mut self, reason: Option<LoginFailureReason>) -> Self {
+        self.failure_reason = reason;
+        self
+    }
+
+    /// 设置账号
+    pub fn with_username(mut self, username: &str) -> Self {
+        self.username = username.to_string();
+        self
+    }
+
+    /// 设置本机IP地址
+    pub fn with_ip(mut self, ip: Option<String>) -> Self {
+        self.ip = ip;
+        self
     }
 
     /// 构建消息结果
     pub fn build(self, config: &MessageConfig) -> MessageResult {
-        let campus_message = self
-            .campus_status
-            .as_ref()
-            .map(|s| s.to_message())
-            .unwrap_or_default();
+        let campus = match &self.failure_reason {
+            Some(reason) => format!("登录校园网失败: {}", reason.to_message()),
+            None => self
+                .campus_status
+                .as_ref()
+                .map(|s| s.to_message())
+                .unwrap_or_default(),
+        };
 
         // 只有在需要显示广域网信息时才显示广域网状态消息
-        let wan_message = if matches!(self.wan_status, Some(WanStatus::CheckFailed)) {
+        let wan = if matches!(self.wan_status, Some(WanStatus::CheckFailed)) {
             String::new()
         } else {
             self.wan_status
@@ -142,46 +183,28 @@ impl MessageBuilder {
                 .unwrap_or_default()
         };
 
-        let time_message = self
-            .elapsed_time
-            .map(|t| format!("用时{:.2}秒", t))
-            .unwrap_or_default();
-
-        let flow_message = if self.isp.is_empty() {
-            // 校园网运营商，显示流量信息
-            self.flow_info.unwrap_or_default()
-        } else {
-            // 非校园网运营商，过滤流量信息
-            String::new()
+        let mut values = TemplateValues {
+            campus,
+            wan,
+            elapsed_secs: self.elapsed_time,
+            username: self.username,
+            isp: isp_display_name(&self.isp),
+            ip: self.ip.unwrap_or_default(),
+            time: Some(Local::now().naive_local()),
+            ..Default::default()
         };
 
-        // 处理占位符替换
-        let notify_message = Self::replace_placeholders(
-            &config.notify_text,
-            &campus_message,
-            &wan_message,
-            &time_message,
-            &flow_message,
-            true,
-        );
-
-        let gui_message = Self::replace_placeholders(
-            &config.gui_text,
-            &campus_message,
-            &wan_message,
-            &time_message,
-            &flow_message,
-            true,
-        );
+        // 只有校园网运营商显示流量和账户信息
+        if self.isp.is_empty() {
+            values.left_flow_mb = self.left_flow;
+            values.forecast = self.flow_forecast.unwrap_or_default();
+            values.balance = self.balance;
+            values.package = self.package.unwrap_or_default();
+        }
 
-        let log_message = Self::replace_placeholders(
-            &config.log_text,
-            &campus_message,
-            &wan_message,
-            &time_message,
-            &flow_message,
-            false,
-        );
+        let notify_message = Self::render_template(&config.notify_text, &values, true);
+        let gui_message = Self::render_template(&config.gui_text, &values, true);
+        let log_message = Self::render_template(&config.log_text, &values, false);
 
         MessageResult {
             notify_message,
@@ -190,22 +213,9 @@ impl MessageBuilder {
         }
     }
 
-    /// 替换占位符
-    fn replace_placeholders(
-        template: &str,
-        campus: &str,
-        wan: &str,
-        time: &str,
-        flow: &str,
-        allow_newlines: bool,
-    ) -> String {
-        let mut result = template.to_string();
-
-        // 替换占位符
-        result = result.replace("%1", campus);
-        result = result.replace("%2", wan);
-        result = result.replace("%3", time);
-        result = result.replace("%4", flow);
+    /// 渲染模板，合并空占位符留下的连续空格
+    fn render_template(template: &str, values: &TemplateValues, allow_newlines: bool) -> String {
+        let mut result = MessageTemplate::parse(template).render(values);
 
         if !allow_newlines {
             result = result.replace('\n', " ");
@@ -214,13 +224,7 @@ impl MessageBuilder {
         while result.contains("  ") {
             result = result.replace("  ", " ");
         }
-        result = result.trim().to_string();
-
-        if wan.is_empty() {
-            result = result.replace("  ", " ").trim().to_string();
-        }
-
-        result
+        result.trim().to_string()
     }
 }
 
@@ -262,26 +266,18 @@ impl MessageCenter {
         elapsed: f64,
         show_notification: bool,
         should_log: bool,
-        flow_info: Option<f64>,
+        account_info: Option<&SelfServiceAccountInfo>,
     ) -> String {
         // 如果没有配置，使用默认消息
         let Some(config) = self.config.as_ref() else {
             return format!("{} {}", campus_status.to_message(), wan_status.to_message());
         };
 
-        let isp = config.account.isp.clone();
-
-        let mut builder = MessageBuilder::new(isp)
+        let result = Self::message_builder(config, &config.account, account_info)
             .with_campus_status(campus_status.clone())
             .with_wan_status(wan_status.clone())
-            .with_elapsed_time(elapsed);
-
-        // 如果有流量信息，添加到构建器
-        if let Some(flow) = flow_info {
-            builder = builder.with_flow_info(flow);
-        }
-
-        let result = builder.build(&config.message);
+            .with_elapsed_time(elapsed)
+            .build(&config.message);
 
         if should_log {
             let _ = self.log_event("INFO", &result.log_message);
@@ -295,32 +291,34 @@ impl MessageCenter {
     }
 
     /// 处理登录结果
+    ///
+    /// `account`为实际登录的账号，使用备用档案或切换档案登录时与默认账号不同
     pub fn handle_login_result(
         &self,
+        account: &AccountConfig,
         campus_status: CampusNetworkStatus,
         wan_status: WanStatus,
         elapsed: f64,
-        success: bool,
-        flow_info: Option<f64>,
+        account_info: Option<&SelfServiceAccountInfo>,
+        failure_reason: Option<LoginFailureReason>,
     ) -> String {
+        let success = campus_status == CampusNetworkStatus::LoginSuccess;
+
         // 如果没有配置，使用默认消息
         let Some(config) = self.config.as_ref() else {
-            return format!("{} {}", campus_status.to_message(), wan_status.to_message());
+            let campus_message = match &failure_reason {
+                Some(reason) => format!("登录校园网失败: {}", reason.to_message()),
+                None => campus_status.to_message(),
+            };
+            return format!("{} {}", campus_message, wan_status.to_message());
         };
 
-        let isp = config.account.isp.clone();
-
-        let mut builder = MessageBuilder::new(isp)
+        let result = Self::message_builder(config, account, account_info)
             .with_campus_status(campus_status)
             .with_wan_status(wan_status)
-            .with_elapsed_time(elapsed);
-
-        // 如果有流量信息，则添加到构建器
-        if let Some(flow) = flow_info {
-            builder = builder.with_flow_info(flow);
-        }
-
-        let result = builder.build(&config.message);
+            .with_elapsed_time(elapsed)
+            .with_failure_reason(failure_reason)
+            .build(&config.message);
 
         let log_level = if success { "INFO" } else { "ERROR" };
         let _ = self.log_event(log_level, &result.log_message);
@@ -330,6 +328,40 @@ impl MessageCenter {
         result.gui_message
     }
 
+    /// 创建消息构建器，按消息模板的需要填入`account`的账号、IP地址、账户信息和流量消耗预测
+    fn message_builder(
+        config: &ConfigData,
+        account: &AccountConfig,
+        account_info: Option<&SelfServiceAccountInfo>,
+    ) -> MessageBuilder {
+        let needs = config.message.needs();
+
+        let mut builder = MessageBuilder::new(account.isp.clone()).with_username(&account.username);
+
+        if needs.ip {
+            builder = builder.with_ip(local_ip(&config.network.login_ip));
+        }
+
+        if let Some(info) = account_info {
+            builder = builder.with_account_info(info);
+            if needs.flow_forecast
+                && let Some(forecast) = Self::flow_forecast(&account.username)
+            {
+                builder = builder.with_flow_forecast(&forecast);
+            }
+        }
+
+        builder
+    }
+
+    /// 读取流量历史计算账号的消耗预测
+    fn flow_forecast(username: &str) -> Option<FlowForecast> {
+        FlowHistory::new(state_file_path(FLOW_HISTORY_FILE_NAME))
+            .forecast(username, Local::now().naive_local())
+            .ok()
+            .flatten()
+    }
+
     /// 处理登录结果但不触发事件（用于静默模式）
     pub fn handle_login_result_without_event(&self, result: LoginResult) -> LoginResult {
         // 如果没有配置，直接返回结果
@@ -337,12 +369,9 @@ impl MessageCenter {
             return result;
         };
 
-        let isp = config.account.isp.clone();
-
         // 构建消息但不触发事件
-        let builder = MessageBuilder::new(isp);
-
-        let message_result = builder.build(&config.message);
+        let message_result =
+            Self::message_builder(config, &config.account, None).build(&config.message);
 
         // 只记录日志，不显示通知
         let log_level = if result.success { "INFO" } else { "ERROR" };
@@ -356,9 +385,25 @@ impl MessageCenter {
         Notification::new()
             .summary(message)
             .show()
+            .map(|_| ())
             .map_err(|e| AppError::NotificationError(e.to_string()))
     }
 
+    /// 检查剩余流量（MB）是否低于`[flow_alert]`中的提醒阈值，新跨过阈值时发送通知
+    pub fn check_flow_alert(&self, left_flow: f64) -> AppResult<()> {
+        let Some(config) = self.config.as_ref() else {
+            return Ok(());
+        };
+
+        let state_path = state_file_path(FLOW_ALERT_STATE_FILE_NAME);
+        if let Some(message) = check_flow_alert(&config.flow_alert, &state_path, left_flow)? {
+            let _ = self.log_event("WARNING", &message);
+            self.show_notification("", &message)?;
+        }
+
+        Ok(())
+    }
+
     /// 记录日志事件
     pub fn log_event(&self, level: &str, message: &str) -> AppResult<()> {
         let Some(config) = self.config.as_ref() else {
@@ -371,7 +416,7 @@ impl MessageCenter {
 
         let _guard = self.write_mutex.lock().unwrap();
 
-        let log_file_path = config.logging.log_file_path.as_str();
+        let log_file_path = config.logging.resolved_log_file_path();
         let log_entry = format!(
             "[{}][{}] {}\n",
             Local::now().format("%Y-%m-%d %H:%M:%S"),
@@ -379,7 +424,7 @@ impl MessageCenter {
             message
         );
 
-        if let Some(parent) = Path::new(log_file_path).parent() {
+        if let Some(parent) = log_file_path.parent() {
             fs::create_dir_all(parent)
                 .map_err(|e| AppError::SystemError(format!("无法创建日志目录: {}", e)))?;
         }
@@ -387,9 +432,13 @@ impl MessageCenter {
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open(log_file_path)
+            .open(&log_file_path)
             .map_err(|e| {
-                AppError::SystemError(format!("无法打开日志文件 '{}': {}", log_file_path, e))
+                AppError::SystemError(format!(
+                    "无法打开日志文件 '{}': {}",
+                    log_file_path.display(),
+                    e
+                ))
             })?;
 
         file.write_all(log_entry.as_bytes())
@@ -401,16 +450,20 @@ impl MessageCenter {
     /// 读取日志
     pub fn read_logs(&self) -> AppResult<String> {
         let log_file_path = match self.config.as_ref() {
-            Some(c) if c.logging.enable_logging => &c.logging.log_file_path,
+            Some(c) if c.logging.enable_logging => c.logging.resolved_log_file_path(),
             _ => return Ok(String::new()),
         };
 
-        if !Path::new(log_file_path).exists() {
+        if !log_file_path.exists() {
             return Ok(String::new());
         }
 
-        let file = File::open(log_file_path).map_err(|e| {
-            AppError::SystemError(format!("无法打开日志文件 '{}': {}", log_file_path, e))
+        let file = File::open(&log_file_path).map_err(|e| {
+            AppError::SystemError(format!(
+                "无法打开日志文件 '{}': {}",
+                log_file_path.display(),
+                e
+            ))
         })?;
 
         let reader = BufReader::new(file);
@@ -438,18 +491,22 @@ impl MessageCenter {
 
         let _guard = self.write_mutex.lock().unwrap();
 
-        let log_file_path = config.logging.log_file_path.as_str();
+        let log_file_path = config.logging.resolved_log_file_path();
 
-        if !Path::new(log_file_path).exists() {
+        if !log_file_path.exists() {
             return Ok(());
         }
 
         let cutoff_date = Local::now() - Duration::days(config.logging.info_log_retention_days);
-        let temp_file_path = format!("{}.tmp", log_file_path);
+        let temp_file_path = format!("{}.tmp", log_file_path.display());
 
         {
-            let input_file = File::open(log_file_path).map_err(|e| {
-                AppError::SystemError(format!("无法打开日志文件 '{}': {}", log_file_path, e))
+            let input_file = File::open(&log_file_path).map_err(|e| {
+                AppError::SystemError(format!(
+                    "无法打开日志文件 '{}': {}",
+                    log_file_path.display(),
+                    e
+                ))
             })?;
             let output_file = File::create(&temp_file_path).map_err(|e| {
                 AppError::SystemError(format!("无法创建临时文件 '{}': {}", temp_file_path, e))
@@ -488,7 +545,7 @@ impl MessageCenter {
                 .map_err(|e| AppError::SystemError(format!("刷新临时文件失败: {}", e)))?;
         }
 
-        fs::rename(&temp_file_path, log_file_path)
+        fs::rename(&temp_file_path, &log_file_path)
             .map_err(|e| AppError::SystemError(format!("替换日志文件失败: {}", e)))?;
 
         let _ = fs::remove_file(&temp_file_path);
@@ -496,3 +553,33 @@ impl MessageCenter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_builder_uses_login_account() {
+        let mut config = ConfigData::default();
+        config.account.username = "2100000000".to_string();
+        config.message.gui_text = "{username} {isp} {flow}".to_string();
+
+        let account_info = SelfServiceAccountInfo {
+            left_flow: 20480.0,
+            ..Default::default()
+        };
+        let result = MessageCenter::message_builder(&config, &config.account, Some(&account_info))
+            .build(&config.message);
+        assert_eq!(result.gui_message, "2100000000 校园网 剩余流量20.00GB");
+
+        // 运营商账号登录时显示该账号，不显示校园网账号的流量
+        let account = AccountConfig {
+            username: "2200000000".to_string(),
+            isp: "cmcc".to_string(),
+            ..Default::default()
+        };
+        let result = MessageCenter::message_builder(&config, &account, Some(&account_info))
+            .build(&config.message);
+        assert_eq!(result.gui_message, "2200000000 中国移动");
+    }
+}
@@ -0,0 +1,229 @@
+//! 配置迁移模块
+//!
+//! 配置文件通过`config_version`记录结构版本，加载时按版本依次执行迁移步骤，
+//! 把旧版本的配置升级到当前的`ConfigData`结构
+//!
+//! 版本历史：
+//! - 0：没有`config_version`字段的早期配置，可能缺少`[message]`节
+//! - 1：包含`[message]`节
+//! - 2：包含`[watch]`、`[network.portal]`、`[network.probes]`和`network.self_service_url`
+
+use crate::core::config::{ConfigData, MessageConfig, WatchConfig};
+use crate::core::error::{AppError, AppResult};
+use crate::core::portal::PortalConfig;
+use crate::core::probe::ProbesConfig;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, value};
+
+/// 当前配置结构版本
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+/// 单个迁移步骤
+struct Migration {
+    /// 迁移前的版本，迁移后版本加一
+    from: u32,
+    /// 执行迁移，返回所做修改的说明
+    apply: fn(&mut DocumentMut) -> AppResult<Vec<String>>,
+}
+
+/// 按版本排列的迁移步骤
+const MIGRATIONS: [Migration; 2] = [
+    Migration {
+        from: 0,
+        apply: add_message_section,
+    },
+    Migration {
+        from: 1,
+        apply: add_watch_portal_and_probes,
+    },
+];
+
+/// 配置加载报告
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrationReport {
+    /// 配置文件原来的版本
+    pub from_version: u32,
+    /// 迁移后的版本
+    pub to_version: u32,
+    /// 迁移前备份的原配置文件
+    pub backup_path: Option<PathBuf>,
+    /// 加载过程中对配置所做的修改
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    /// 是否执行了版本迁移
+    pub fn is_migrated(&self) -> bool {
+        self.from_version != self.to_version
+    }
+
+    /// 是否修改了配置
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// 转换为消息文本
+    pub fn to_message(&self) -> String {
+        if !self.has_changes() {
+            return "配置加载成功".to_string();
+        }
+
+        let mut message = if self.is_migrated() {
+            format!(
+                "配置已从版本{}升级到版本{}",
+                self.from_version, self.to_version
+            )
+        } else {
+            "配置已更新".to_string()
+        };
+
+        if let Some(backup_path) = &self.backup_path {
+            message.push_str(&format!("，原配置已备份到{}", backup_path.display()));
+        }
+        message.push_str(&format!("：{}", self.changes.join("；")));
+
+        message
+    }
+}
+
+/// 读取配置文件中的版本号，缺失时视为版本0
+pub fn config_version(document: &DocumentMut) -> u32 {
+    document
+        .get("config_version")
+        .and_then(Item::as_integer)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(0)
+}
+
+/// 把配置文档迁移到当前版本
+///
+/// 配置文件版本高于当前程序时不做修改，未知字段会在解析时被忽略
+pub fn migrate(document: &mut DocumentMut) -> AppResult<MigrationReport> {
+    let from_version = config_version(document);
+    let mut report = MigrationReport {
+        from_version,
+        to_version: from_version,
+        ..Default::default()
+    };
+
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from_version) {
+        report.changes.extend((migration.apply)(document)?);
+        report.to_version = migration.from + 1;
+    }
+
+    if report.is_migrated() {
+        document["config_version"] = value(i64::from(report.to_version));
+    }
+
+    Ok(report)
+}
+
+/// 迁移前备份原配置文件，备份文件名带有原版本号，如`config.toml.v0.bak`
+pub fn backup_config_file(config_path: &Path, version: u32) -> AppResult<PathBuf> {
+    let file_name = config_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup_path = config_path.with_file_name(format!("{}.v{}.bak", file_name, version));
+
+    fs::copy(config_path, &backup_path)
+        .map_err(|e| AppError::ConfigError(format!("无法备份配置文件: {}", e)))?;
+
+    Ok(backup_path)
+}
+
+/// 版本0 -> 1：缺少`[message]`节时按运营商类型补充默认消息模板
+fn add_message_section(document: &mut DocumentMut) -> AppResult<Vec<String>> {
+    if document.contains_key("message") {
+        return Ok(Vec::new());
+    }
+
+    let isp = document
+        .get("account")
+        .and_then(|account| account.get("isp"))
+        .and_then(Item::as_str)
+        .unwrap_or_default();
+    let message = if isp.is_empty() {
+        MessageConfig::for_campus_network()
+    } else {
+        MessageConfig::for_non_campus_network()
+    };
+
+    document.insert("message", Item::Table(to_table(&message)?));
+    Ok(vec!["补充[message]消息模板".to_string()])
+}
+
+/// 版本1 -> 2：补充守护模式、认证系统驱动、广域网探测和自助服务系统地址的默认配置
+fn add_watch_portal_and_probes(document: &mut DocumentMut) -> AppResult<Vec<String>> {
+    let mut changes = Vec::new();
+    let defaults = ConfigData::default();
+
+    if !document.contains_key("watch") {
+        document.insert("watch", Item::Table(to_table(&WatchConfig::default())?));
+        changes.push("补充[watch]守护模式配置".to_string());
+    }
+
+    // 没有[network]节时加载后整节使用默认值，无需补充
+    let Some(network) = document.get_mut("network") else {
+        return Ok(changes);
+    };
+    let network = network
+        .as_table_mut()
+        .ok_or_else(|| AppError::ConfigError("[network]不是表".to_string()))?;
+
+    if !network.contains_key("self_service_url") {
+        network.insert("self_service_url", value(defaults.network.self_service_url));
+        changes.push("补充network.self_service_url自助服务系统地址".to_string());
+    }
+    if !network.contains_key("portal") {
+        network.insert("portal", Item::Table(to_table(&PortalConfig::default())?));
+        changes.push("补充[network.portal]认证系统配置".to_string());
+    }
+    if !network.contains_key("probes") {
+        network.insert("probes", Item::Table(to_table(&ProbesConfig::default())?));
+        changes.push("补充[network.probes]广域网探测配置".to_string());
+    }
+
+    Ok(changes)
+}
+
+/// 把配置结构序列化为TOML表
+fn to_table<T: Serialize>(config: &T) -> AppResult<Table> {
+    let content = toml::to_string(config)
+        .map_err(|e| AppError::ConfigError(format!("无法序列化配置: {}", e)))?;
+    let document = content
+        .parse::<DocumentMut>()
+        .map_err(|e| AppError::ConfigError(format!("无法序列化配置: {}", e)))?;
+
+    let mut table = document.as_table().clone();
+    table.set_implicit(false);
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_unversioned_config() {
+        let mut document = "[account]\nusername = \"2100000000\"\nencrypted_password = \"\"\nisp = \"cmcc\"\n\n[network]\nlogin_ip = \"http://10.0.1.5/\"\nresult_return = '\"result\":1'\nsigned_in_title = \"注销页\"\nnot_sign_in_title = \"上网登录页\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        let report = migrate(&mut document).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, CURRENT_CONFIG_VERSION);
+        assert_eq!(report.changes.len(), 5);
+
+        let config: ConfigData = toml::from_str(&document.to_string()).unwrap();
+        assert_eq!(config.config_version, CURRENT_CONFIG_VERSION);
+        assert_eq!(config.message, MessageConfig::for_non_campus_network());
+        assert_eq!(config.network.probes, ProbesConfig::default());
+
+        let report = migrate(&mut document).unwrap();
+        assert!(!report.is_migrated());
+        assert!(!report.has_changes());
+    }
+}
@@ -2,15 +2,31 @@
 
 pub mod config;
 pub mod crypto;
+pub mod drcom;
 pub mod dto;
 pub mod error;
 pub mod events;
 pub mod flow;
+pub mod flow_alert;
+pub mod flow_history;
+pub mod flow_switch;
+pub mod machine_key;
 pub mod message;
+pub mod migration;
 pub mod network;
+pub mod passphrase;
+pub mod paths;
+pub mod portal;
+pub mod probe;
+pub mod secret_store;
+pub mod secret_string;
 pub mod service;
+pub mod template;
+pub mod watch;
 
-pub use config::{is_config_complete, load_config, normalize_isp, save_config};
+pub use config::{
+    is_config_complete, load_config, load_config_with_report, normalize_isp, save_config,
+};
 pub use crypto::{
     decrypt_password, decrypt_password_with_machine_key, encrypt_password, generate_machine_key,
 };
@@ -24,4 +40,4 @@ pub use events::{
 };
 pub use message::MessageCenter;
 pub use network::{NetworkManager, is_login_successful};
-pub use service::{AuthService, LoginResult};
+pub use service::{AuthService, LoginResult, LogoutResult};
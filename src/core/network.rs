@@ -3,28 +3,19 @@
 //! 负责处理应用程序的所有网络相关功能
 
 use crate::core::config::normalize_isp;
-use crate::core::error::{AppError, AppResult, NetworkError};
+use crate::core::drcom::{DrcomLoginResponse, LoginFailureReason};
+use crate::core::error::AppResult;
 use crate::core::message::{CampusNetworkStatus, WanStatus};
+use crate::core::portal::{PortalConfig, PortalDriver, create_driver};
+use crate::core::probe::ProbesConfig;
+use crate::core::secret_string::SecretString;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::net::UdpSocket;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use urlencoding::encode;
-
-const EXTERNAL_SITES: [&str; 5] = [
-    "https://www.baidu.com",
-    "https://www.qq.com",
-    "https://www.sina.com.cn",
-    "https://www.alibaba.com",
-    "https://www.bytedance.com/",
-];
-
-// 限制响应体大小为10MB（对于校园网认证足够大，同时防止极端情况）
-const MAX_RESPONSE_SIZE: usize = 10 * 1024 * 1024;
-
-const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/139.0.0.0 Safari/537.36";
+use tokio::task::JoinSet;
 
 /// 网络配置结构体
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -37,6 +28,15 @@ pub struct NetworkConfig {
     pub signed_in_title: String,
     /// 未登录页面标题标识
     pub not_sign_in_title: String,
+    /// 用户自助服务系统地址（查询流量）
+    #[serde(default = "default_self_service_url")]
+    pub self_service_url: String,
+    /// 认证系统配置
+    #[serde(default)]
+    pub portal: PortalConfig,
+    /// 广域网探测配置
+    #[serde(default)]
+    pub probes: ProbesConfig,
 }
 
 impl Default for NetworkConfig {
@@ -46,15 +46,26 @@ impl Default for NetworkConfig {
             result_return: "\"result\":1".to_string(),
             signed_in_title: "注销页".to_string(),
             not_sign_in_title: "上网登录页".to_string(),
+            self_service_url: default_self_service_url(),
+            portal: PortalConfig::default(),
+            probes: ProbesConfig::default(),
         }
     }
 }
 
+/// 默认的用户自助服务系统地址
+fn default_self_service_url() -> String {
+    "https://nicdrcom.guet.edu.cn/Self".to_string()
+}
+
 /// 网络管理器
+///
+/// 登录、注销和校园网状态检测交给`[network.portal]`配置的认证系统驱动完成
 #[derive(Debug, Clone)]
 pub struct NetworkManager {
     client: Client,
     config: NetworkConfig,
+    driver: Arc<dyn PortalDriver>,
 }
 
 impl NetworkManager {
@@ -65,7 +76,13 @@ impl NetworkManager {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, config }
+        let driver = create_driver(&config);
+
+        Self {
+            client,
+            config,
+            driver,
+        }
     }
 
     fn get_client(&self) -> &Client {
@@ -74,206 +91,173 @@ impl NetworkManager {
 
     /// 检查校园网状态
     pub async fn check_campus_network(&self) -> AppResult<CampusNetworkStatus> {
-        let response = self
-            .get_client()
-            .get(&self.config.login_ip)
-            .send()
-            .await
-            .map_err(|e| AppError::NetworkError {
-                source: crate::core::error::map_reqwest_error(e),
-            })?;
-
-        let text = response.text().await.map_err(|e| AppError::NetworkError {
-            source: NetworkError::Other(e.to_string()),
-        })?;
-
-        let truncated_text = if text.len() > 4096 {
-            text[..4096].to_string()
-        } else {
-            text
-        };
-
-        if truncated_text.contains(&self.config.signed_in_title) {
-            Ok(CampusNetworkStatus::AlreadyLoggedIn)
-        } else if truncated_text.contains(&self.config.not_sign_in_title) {
-            Ok(CampusNetworkStatus::NotLoggedIn)
-        } else {
-            // 当无法识别校园网页面时，视为未登录
-            Ok(CampusNetworkStatus::NotLoggedIn)
-        }
+        self.driver.check_status(self.get_client()).await
     }
 
     /// 检查广域网状态
     pub async fn check_wan_network(&self) -> WanStatus {
-        let result = self.check_external_connectivity().await;
-
-        if result {
-            WanStatus::Connected
-        } else {
-            WanStatus::Disconnected
-        }
+        self.check_wan_network_detailed().await.status
     }
 
-    /// 检查外部网络连接状态
-    async fn check_external_connectivity(&self) -> bool {
-        let cancelled = Arc::new(AtomicBool::new(false));
+    /// 检查广域网状态并返回每个探测目标的耗时
+    ///
+    /// 按`[network.probes]`配置并发执行所有探测，任一目标可访问即返回并取消其余仍在进行的请求，
+    /// 网络不可用时总耗时约为单个探测的超时时间
+    pub async fn check_wan_network_detailed(&self) -> WanCheckResult {
+        let start_time = std::time::Instant::now();
+        let targets = &self.config.probes.targets;
+
+        if targets.is_empty() {
+            return WanCheckResult {
+                status: WanStatus::CheckFailed,
+                probes: Vec::new(),
+                elapsed_time: 0.0,
+            };
+        }
 
-        let mut futures = Vec::new();
+        let timeout = Duration::from_secs(self.config.probes.timeout_secs.max(1));
+        let mut probes: Vec<Option<ProbeResult>> = vec![None; targets.len()];
+        let mut tasks = JoinSet::new();
 
-        for &site in &EXTERNAL_SITES {
+        for (index, target) in targets.iter().enumerate() {
             let client = self.get_client().clone();
-            let cancelled_clone = cancelled.clone();
+            let target = target.clone();
             let login_ip = self.config.login_ip.clone();
             let not_sign_in_title = self.config.not_sign_in_title.clone();
 
-            let future = async move {
-                if cancelled_clone.load(Ordering::Relaxed) {
-                    return false;
-                }
-
-                let result =
-                    tokio::time::timeout(Duration::from_secs(5), client.get(site).send()).await;
-
-                match result {
-                    Ok(Ok(response)) => {
-                        let status = response.status();
-
-                        // 检查是否被重定向到校园网登录页面
-                        if let Some(url) = response.url().host_str()
-                            && login_ip.contains(url)
-                        {
-                            // 被重定向到校园网登录页面，说明未连接外网
-                            return false;
-                        }
-
-                        // 检查响应内容是否包含登录页面标题
-                        let text_result =
-                            tokio::time::timeout(Duration::from_secs(3), response.text()).await;
-
-                        if let Ok(Ok(text)) = text_result
-                            && text.contains(&not_sign_in_title)
-                        {
-                            // 响应内容是登录页面，说明未连接外网
-                            return false;
-                        }
-
-                        status.is_success()
-                    }
-                    _ => false,
-                }
+            tasks.spawn(async move {
+                let probe_start = std::time::Instant::now();
+                let reachable = target
+                    .probe(&client, timeout, &login_ip, &not_sign_in_title)
+                    .await;
+                (index, reachable, probe_start.elapsed().as_secs_f64())
+            });
+        }
+
+        let mut connected = false;
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((index, reachable, elapsed_time)) = joined else {
+                continue;
             };
 
-            futures.push(future);
-        }
+            probes[index] = Some(ProbeResult {
+                target: targets[index].describe(),
+                outcome: if reachable {
+                    ProbeOutcome::Reachable
+                } else {
+                    ProbeOutcome::Unreachable
+                },
+                elapsed_time,
+            });
 
-        for future in futures {
-            if future.await {
-                // 一旦有一个网站可以访问，就取消其他请求并返回true
-                cancelled.store(true, Ordering::Relaxed);
-                return true;
+            if reachable {
+                connected = true;
+                break;
             }
         }
 
-        false
+        // 一旦有一个目标可以访问，就中止其余仍在进行的探测
+        tasks.abort_all();
+        let elapsed_time = start_time.elapsed().as_secs_f64();
+
+        let probes = probes
+            .into_iter()
+            .enumerate()
+            .map(|(index, probe)| {
+                probe.unwrap_or_else(|| ProbeResult {
+                    target: targets[index].describe(),
+                    outcome: ProbeOutcome::Cancelled,
+                    elapsed_time,
+                })
+            })
+            .collect();
+
+        WanCheckResult {
+            status: if connected {
+                WanStatus::Connected
+            } else {
+                WanStatus::Disconnected
+            },
+            probes,
+            elapsed_time,
+        }
     }
 
     /// 使用凭据尝试登录
     pub async fn attempt_login_with_credentials(
         &self,
         username: &str,
-        password: &str,
+        password: &SecretString,
         isp: &str,
     ) -> AppResult<LoginResult> {
         let start_time = std::time::Instant::now();
 
         let result = self
-            .try_drcom_login(username, password, &normalize_isp(isp))
+            .driver
+            .login(self.get_client(), username, password, &normalize_isp(isp))
             .await;
 
         let elapsed_time = start_time.elapsed().as_secs_f64();
 
         match result {
-            Ok(response_text) => {
-                if is_login_successful(&response_text) {
-                    Ok(LoginResult {
-                        success: true,
-                        campus_status: CampusNetworkStatus::LoginSuccess,
-                        elapsed_time,
-                    })
+            Ok(outcome) => Ok(LoginResult {
+                success: outcome.success,
+                campus_status: if outcome.success {
+                    CampusNetworkStatus::LoginSuccess
                 } else {
-                    Ok(LoginResult {
-                        success: false,
-                        campus_status: CampusNetworkStatus::NotLoggedIn,
-                        elapsed_time,
-                    })
-                }
-            }
+                    CampusNetworkStatus::NotLoggedIn
+                },
+                elapsed_time,
+                failure_reason: outcome.failure_reason,
+                response: outcome.response,
+            }),
             Err(_e) => Ok(LoginResult {
                 success: false,
                 campus_status: CampusNetworkStatus::NotLoggedIn,
                 elapsed_time,
+                failure_reason: None,
+                response: None,
             }),
         }
     }
 
-    async fn try_drcom_login(
-        &self,
-        username: &str,
-        password: &str,
-        isp: &str,
-    ) -> AppResult<String> {
-        // URL编码密码
-        let encoded_password = encode(password);
-
-        // 构造（学/工号+运营商）
-        let full_username = if isp.is_empty() {
-            username.to_string()
-        } else {
-            format!("{}@{}", username, isp)
-        };
-
-        // 构造URL参数
-        let params = format!(
-            "callback=dr1003&DDDDD={}&upass={}&0MKKey=123456",
-            full_username, encoded_password
-        );
-        let base_url = &self.config.login_ip;
-        let url = format!("{}/drcom/login?{}", base_url.trim_end_matches('/'), params);
-
-        let request_builder = self
-            .get_client()
-            .get(&url)
-            .header("User-Agent", DEFAULT_USER_AGENT)
-            .header("Referer", &self.config.login_ip);
-
-        let response = request_builder
-            .send()
-            .await
-            .map_err(|e| AppError::NetworkError {
-                source: crate::core::error::map_reqwest_error(e),
-            })?;
-
-        let content_length = response.content_length().unwrap_or(0) as usize;
-        if content_length > MAX_RESPONSE_SIZE {
-            return Err(AppError::NetworkError {
-                source: NetworkError::Other("响应体过大".to_string()),
-            });
-        }
+    /// 注销校园网
+    ///
+    /// 发送注销请求后重新获取认证页面，页面回到未登录标题才视为注销成功
+    pub async fn logout(&self) -> AppResult<LogoutResult> {
+        let start_time = std::time::Instant::now();
 
-        let text = response.text().await.map_err(|e| AppError::NetworkError {
-            source: NetworkError::Other(e.to_string()),
-        })?;
+        let signed_out = self.driver.logout(self.get_client()).await?;
+        let elapsed_time = start_time.elapsed().as_secs_f64();
 
-        let relevant_text = if text.len() > 8192 {
-            text[..8192].to_string()
-        } else {
-            text
-        };
+        Ok(LogoutResult {
+            success: signed_out,
+            campus_status: if signed_out {
+                CampusNetworkStatus::NotLoggedIn
+            } else {
+                CampusNetworkStatus::AlreadyLoggedIn
+            },
+            elapsed_time,
+        })
+    }
 
-        Ok(relevant_text)
+    /// 当前使用的认证系统驱动名称
+    pub fn driver_name(&self) -> &'static str {
+        self.driver.name()
     }
 }
 
+/// 注销结果
+#[derive(Debug, Clone)]
+pub struct LogoutResult {
+    /// 是否注销成功（认证页面已回到未登录状态）
+    pub success: bool,
+    /// 注销后的校园网状态
+    pub campus_status: CampusNetworkStatus,
+    /// 耗时（秒）
+    pub elapsed_time: f64,
+}
+
 /// 登录结果
 #[derive(Debug, Clone)]
 pub struct LoginResult {
@@ -283,6 +267,63 @@ pub struct LoginResult {
     pub campus_status: CampusNetworkStatus,
     /// 耗时（秒）
     pub elapsed_time: f64,
+    /// 登录失败原因（仅在认证服务器返回了可识别的错误时存在）
+    pub failure_reason: Option<LoginFailureReason>,
+    /// 解析后的认证服务器响应
+    pub response: Option<DrcomLoginResponse>,
+}
+
+/// 广域网探测结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeOutcome {
+    /// 目标可访问
+    Reachable,
+    /// 目标不可访问、超时或被认证系统拦截
+    Unreachable,
+    /// 其他目标已确认可访问，请求被中止
+    Cancelled,
+}
+
+impl ProbeOutcome {
+    /// 转换为消息文本
+    pub fn to_message(&self) -> String {
+        match self {
+            ProbeOutcome::Reachable => "可访问".to_string(),
+            ProbeOutcome::Unreachable => "不可访问".to_string(),
+            ProbeOutcome::Cancelled => "已取消".to_string(),
+        }
+    }
+}
+
+/// 单个探测目标的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeResult {
+    /// 探测目标
+    pub target: String,
+    /// 探测结果
+    pub outcome: ProbeOutcome,
+    /// 耗时（秒）
+    pub elapsed_time: f64,
+}
+
+/// 广域网检查结果
+#[derive(Debug, Clone)]
+pub struct WanCheckResult {
+    /// 广域网状态
+    pub status: WanStatus,
+    /// 各探测目标的结果，顺序与配置中的探测目标一致
+    pub probes: Vec<ProbeResult>,
+    /// 总耗时（秒）
+    pub elapsed_time: f64,
+}
+
+impl WanCheckResult {
+    /// 探测成功的目标
+    pub fn successful_probe(&self) -> Option<&ProbeResult> {
+        self.probes
+            .iter()
+            .find(|probe| probe.outcome == ProbeOutcome::Reachable)
+    }
 }
 
 /// 判断登录是否成功
@@ -293,6 +334,19 @@ pub fn is_login_successful(login_text: &str) -> bool {
         || login_text.contains("\"result\":1")
 }
 
+/// 本机访问认证系统时使用的IP地址
+///
+/// 只根据路由表选择本地地址，不会发送数据包，无法确定时返回`None`
+pub fn local_ip(login_ip: &str) -> Option<String> {
+    let url = reqwest::Url::parse(login_ip).ok()?;
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect((host, port)).ok()?;
+    Some(socket.local_addr().ok()?.ip().to_string())
+}
+
 #[async_trait]
 pub trait NetworkManagerTrait: Send + Sync {
     /// 检查校园网状态
@@ -305,10 +359,13 @@ pub trait NetworkManagerTrait: Send + Sync {
     async fn attempt_login_with_credentials(
         &self,
         username: &str,
-        password: &str,
+        password: &SecretString,
         isp: &str,
     ) -> AppResult<LoginResult>;
 
+    /// 注销校园网
+    async fn logout(&self) -> AppResult<LogoutResult>;
+
     /// 克隆网络管理器
     fn clone_box(&self) -> Box<dyn NetworkManagerTrait>;
 }
@@ -326,13 +383,17 @@ impl NetworkManagerTrait for NetworkManager {
     async fn attempt_login_with_credentials(
         &self,
         username: &str,
-        password: &str,
+        password: &SecretString,
         isp: &str,
     ) -> AppResult<LoginResult> {
         self.attempt_login_with_credentials(username, password, isp)
             .await
     }
 
+    async fn logout(&self) -> AppResult<LogoutResult> {
+        self.logout().await
+    }
+
     fn clone_box(&self) -> Box<dyn NetworkManagerTrait> {
         Box::new(self.clone())
     }
@@ -0,0 +1,91 @@
+//! 主密码模块
+//!
+//! 主密码模式下配置中的密码使用主密码派生的密钥加密，不与机器绑定，配置文件可以复制到其他电脑使用
+//!
+//! 主密码在每次运行时提供一次，按以下顺序获取：
+//! 1. 本次运行中已输入的主密码（命令行提示输入或从文件描述符读取）
+//! 2. 环境变量`AUTOLOGINGUET_PASSPHRASE`
+
+use crate::core::error::{AppError, AppResult};
+use crate::core::secret_string::SecretString;
+use lazy_static::lazy_static;
+use std::env;
+use std::sync::Mutex;
+
+/// 提供主密码的环境变量
+pub const PASSPHRASE_ENV: &str = "AUTOLOGINGUET_PASSPHRASE";
+
+lazy_static! {
+    static ref SESSION_PASSPHRASE: Mutex<Option<SecretString>> = Mutex::new(None);
+}
+
+/// 设置本次运行使用的主密码
+pub fn set_session_passphrase(passphrase: SecretString) {
+    *SESSION_PASSPHRASE.lock().unwrap() = Some(passphrase);
+}
+
+/// 获取本次运行使用的主密码，未设置时读取环境变量，均不可用时返回`None`
+pub fn session_passphrase() -> Option<SecretString> {
+    if let Some(passphrase) = SESSION_PASSPHRASE.lock().unwrap().clone() {
+        return Some(passphrase);
+    }
+
+    env::var(PASSPHRASE_ENV)
+        .ok()
+        .filter(|passphrase| !passphrase.is_empty())
+        .map(SecretString::new)
+}
+
+/// 从文件描述符读取主密码（第一行，不含换行符），供守护进程使用
+///
+/// 逐字节读取到第一个换行符为止，不等待管道关闭，也不会多读换行符之后的内容
+#[cfg(unix)]
+pub fn read_passphrase_fd(fd: u32) -> AppResult<SecretString> {
+    use std::io::Read;
+    use zeroize::Zeroizing;
+
+    let mut file = std::fs::File::open(format!("/dev/fd/{}", fd))
+        .map_err(|e| AppError::ConfigError(format!("无法打开文件描述符{}: {}", fd, e)))?;
+
+    let mut line = Zeroizing::new(Vec::with_capacity(256));
+    let mut byte = [0u8; 1];
+    loop {
+        match file.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) if byte[0] == b'\n' => break,
+            Ok(_) => line.push(byte[0]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                return Err(AppError::ConfigError(format!(
+                    "无法从文件描述符{}读取主密码: {}",
+                    fd, e
+                )));
+            }
+        }
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    let passphrase = std::str::from_utf8(&line)
+        .map(SecretString::from)
+        .map_err(|_| AppError::ConfigError(format!("文件描述符{}中的主密码不是UTF-8文本", fd)))?;
+    if passphrase.is_empty() {
+        return Err(AppError::ConfigError(format!(
+            "文件描述符{}中的主密码为空",
+            fd
+        )));
+    }
+    Ok(passphrase)
+}
+
+/// 从文件描述符读取主密码
+///
+/// Windows平台不支持，请改用环境变量`AUTOLOGINGUET_PASSPHRASE`
+#[cfg(not(unix))]
+pub fn read_passphrase_fd(_fd: u32) -> AppResult<SecretString> {
+    Err(AppError::ConfigError(format!(
+        "当前平台不支持从文件描述符读取主密码，请使用环境变量{}",
+        PASSPHRASE_ENV
+    )))
+}
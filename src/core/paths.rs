@@ -0,0 +1,235 @@
+//! 配置和日志路径模块
+//!
+//! 配置文件按以下顺序确定位置：
+//! 1. 命令行参数`--config <path>`
+//! 2. 环境变量`AUTOLOGINGUET_CONFIG`
+//! 3. 程序所在目录下已有的`config.toml`（便携模式）
+//! 4. 平台配置目录：Windows为`%APPDATA%\AutoLoginGuet\config.toml`，
+//!    其他平台为`$XDG_CONFIG_HOME/autologinguet/config.toml`
+//!
+//! 日志文件路径为相对路径时以配置文件所在目录为基准，程序状态文件与日志放在同一位置
+
+use lazy_static::lazy_static;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 指定配置文件路径的环境变量
+pub const CONFIG_ENV_VAR: &str = "AUTOLOGINGUET_CONFIG";
+
+/// 配置文件名
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 日志文件名
+pub const LOG_FILE_NAME: &str = "AutoLogin.log";
+
+/// 便携模式下的默认日志路径（相对于配置文件所在目录）
+const PORTABLE_LOG_FILE_PATH: &str = "./AutoLogin.log";
+
+#[cfg(windows)]
+const APP_DIR_NAME: &str = "AutoLoginGuet";
+#[cfg(not(windows))]
+const APP_DIR_NAME: &str = "autologinguet";
+
+lazy_static! {
+    static ref CONFIG_PATH_OVERRIDE: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// 配置文件位置的来源
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigPathSource {
+    /// 命令行参数`--config`
+    Argument,
+    /// 环境变量`AUTOLOGINGUET_CONFIG`
+    Environment,
+    /// 程序所在目录（便携模式）
+    Portable,
+    /// 平台配置目录
+    Platform,
+    /// 无法确定平台配置目录时使用工作目录
+    WorkingDirectory,
+}
+
+impl ConfigPathSource {
+    /// 转换为消息文本
+    pub fn to_message(&self) -> &'static str {
+        match self {
+            ConfigPathSource::Argument => "命令行参数",
+            ConfigPathSource::Environment => "环境变量",
+            ConfigPathSource::Portable => "程序所在目录",
+            ConfigPathSource::Platform => "系统配置目录",
+            ConfigPathSource::WorkingDirectory => "工作目录",
+        }
+    }
+}
+
+/// 配置文件位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLocation {
+    /// 配置文件路径
+    pub path: PathBuf,
+    /// 路径来源
+    pub source: ConfigPathSource,
+}
+
+/// 指定配置文件路径，优先于环境变量和默认位置
+pub fn set_config_path(path: impl Into<PathBuf>) {
+    *CONFIG_PATH_OVERRIDE.lock().unwrap() = Some(path.into());
+}
+
+/// 读取命令行中`--config <path>`或`--config=<path>`指定的配置文件路径
+pub fn config_path_from_args(args: &[String]) -> Option<PathBuf> {
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--config" {
+            return iter.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// 开机自启命令中附加的配置文件参数
+///
+/// 通过`--config`指定配置文件时，开机自启也使用同一份配置
+pub fn auto_start_config_arg() -> String {
+    let location = config_location();
+    if location.source != ConfigPathSource::Argument {
+        return String::new();
+    }
+
+    let path = location
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| location.path.clone());
+    format!(" --config \"{}\"", path.display())
+}
+
+/// 确定配置文件位置
+pub fn config_location() -> ConfigLocation {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.lock().unwrap().clone() {
+        return ConfigLocation {
+            path,
+            source: ConfigPathSource::Argument,
+        };
+    }
+
+    if let Some(path) = env::var_os(CONFIG_ENV_VAR).filter(|path| !path.is_empty()) {
+        return ConfigLocation {
+            path: PathBuf::from(path),
+            source: ConfigPathSource::Environment,
+        };
+    }
+
+    // 程序目录下已有配置文件时沿用便携模式
+    if let Some(path) = exe_dir().map(|dir| dir.join(CONFIG_FILE_NAME))
+        && path.is_file()
+    {
+        return ConfigLocation {
+            path,
+            source: ConfigPathSource::Portable,
+        };
+    }
+
+    match platform_config_dir() {
+        Some(dir) => ConfigLocation {
+            path: dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME),
+            source: ConfigPathSource::Platform,
+        },
+        None => ConfigLocation {
+            path: PathBuf::from(CONFIG_FILE_NAME),
+            source: ConfigPathSource::WorkingDirectory,
+        },
+    }
+}
+
+/// 配置文件所在目录
+pub fn config_dir() -> PathBuf {
+    match config_location().path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// 新配置的默认日志路径
+///
+/// 使用平台配置目录时日志写入平台状态目录，其他情况下与配置文件放在同一目录
+pub fn default_log_file_path() -> String {
+    let location = config_location();
+    if location.source == ConfigPathSource::Platform
+        && let Some(dir) = platform_state_dir()
+    {
+        return dir
+            .join(APP_DIR_NAME)
+            .join(LOG_FILE_NAME)
+            .to_string_lossy()
+            .to_string();
+    }
+
+    PORTABLE_LOG_FILE_PATH.to_string()
+}
+
+/// 程序状态文件路径
+///
+/// 与日志相同，使用平台配置目录时写入平台状态目录，其他情况下与配置文件放在同一目录
+pub fn state_file_path(file_name: &str) -> PathBuf {
+    let location = config_location();
+    if location.source == ConfigPathSource::Platform
+        && let Some(dir) = platform_state_dir()
+    {
+        return dir.join(APP_DIR_NAME).join(file_name);
+    }
+
+    config_dir().join(file_name)
+}
+
+/// 解析日志文件路径，相对路径以配置文件所在目录为基准
+pub fn resolve_log_file_path(log_file_path: &str) -> PathBuf {
+    let path = Path::new(log_file_path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    config_dir().join(path.strip_prefix(".").unwrap_or(path))
+}
+
+/// 程序所在目录
+fn exe_dir() -> Option<PathBuf> {
+    env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+}
+
+/// 平台配置目录（`%APPDATA%`）
+#[cfg(windows)]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_dir("APPDATA")
+}
+
+/// 平台状态目录（`%LOCALAPPDATA%`）
+#[cfg(windows)]
+fn platform_state_dir() -> Option<PathBuf> {
+    env_dir("LOCALAPPDATA")
+}
+
+/// 平台配置目录（`$XDG_CONFIG_HOME`，默认为`~/.config`）
+#[cfg(not(windows))]
+fn platform_config_dir() -> Option<PathBuf> {
+    env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+}
+
+/// 平台状态目录（`$XDG_STATE_HOME`，默认为`~/.local/state`）
+#[cfg(not(windows))]
+fn platform_state_dir() -> Option<PathBuf> {
+    env_dir("XDG_STATE_HOME")
+        .or_else(|| env_dir("HOME").map(|home| home.join(".local").join("state")))
+}
+
+/// 读取环境变量中的目录，忽略空值和相对路径
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+}
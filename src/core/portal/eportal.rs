@@ -0,0 +1,171 @@
+//! 通用Dr.COM网页认证驱动
+//!
+//! 较旧的Dr.COM认证系统没有JSONP接口，登录通过POST提交网页表单，
+//! 成功后跳转到`认证成功页`，失败页面的脚本中带有`Msg=01;`和`msga='...'`
+
+use super::{
+    DEFAULT_USER_AGENT, PortalDriver, PortalLoginOutcome, fetch_portal_page, is_signed_out,
+    page_status, portal_url, read_response_text,
+};
+use crate::core::drcom::DrcomLoginResponse;
+use crate::core::error::{AppError, AppResult};
+use crate::core::message::CampusNetworkStatus;
+use crate::core::network::NetworkConfig;
+use crate::core::secret_string::SecretString;
+use async_trait::async_trait;
+use reqwest::Client;
+
+/// 默认登录表单提交路径
+const DEFAULT_LOGIN_PATH: &str = "/0.htm";
+
+/// 注销页面路径
+const LOGOUT_PATH: &str = "/F.htm";
+
+/// 登录成功页面的标识
+const SUCCESS_MARKERS: [&str; 3] = [
+    "认证成功页",
+    "Dr.COMWebLoginID_3.htm",
+    "successfully logged",
+];
+
+/// 通用Dr.COM网页认证驱动
+#[derive(Debug, Clone)]
+pub struct EportalDriver {
+    config: NetworkConfig,
+}
+
+impl EportalDriver {
+    /// 创建新的驱动实例
+    pub fn new(config: NetworkConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl PortalDriver for EportalDriver {
+    fn name(&self) -> &'static str {
+        "eportal"
+    }
+
+    async fn check_status(&self, client: &Client) -> AppResult<CampusNetworkStatus> {
+        let page = fetch_portal_page(client, &self.config).await?;
+        Ok(page_status(&page, &self.config))
+    }
+
+    async fn login(
+        &self,
+        client: &Client,
+        username: &str,
+        password: &SecretString,
+        isp: &str,
+    ) -> AppResult<PortalLoginOutcome> {
+        let portal = &self.config.portal;
+        let full_username = portal.full_username(username, isp);
+        let form = [
+            ("DDDDD", full_username.as_str()),
+            ("upass", password.expose()),
+            ("0MKKey", portal.mkkey.as_str()),
+            ("R1", "0"),
+            ("R2", ""),
+            ("R3", "0"),
+            ("R6", "0"),
+            ("para", "00"),
+            ("v6ip", ""),
+        ];
+        let path = portal.login_path.as_deref().unwrap_or(DEFAULT_LOGIN_PATH);
+
+        let response = client
+            .post(portal_url(&self.config.login_ip, path))
+            .header("User-Agent", DEFAULT_USER_AGENT)
+            .header("Referer", &self.config.login_ip)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: crate::core::error::map_reqwest_error(e),
+            })?;
+
+        let text = read_response_text(response, 8192).await?;
+        Ok(self.parse_login_response(&text))
+    }
+
+    async fn logout(&self, client: &Client) -> AppResult<bool> {
+        client
+            .get(portal_url(&self.config.login_ip, LOGOUT_PATH))
+            .header("User-Agent", DEFAULT_USER_AGENT)
+            .header("Referer", &self.config.login_ip)
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: crate::core::error::map_reqwest_error(e),
+            })?;
+
+        let page = fetch_portal_page(client, &self.config).await?;
+        Ok(is_signed_out(&page, &self.config))
+    }
+
+    fn parse_login_response(&self, text: &str) -> PortalLoginOutcome {
+        if SUCCESS_MARKERS.iter().any(|marker| text.contains(marker)) {
+            return PortalLoginOutcome {
+                success: true,
+                failure_reason: None,
+                response: None,
+            };
+        }
+
+        // 失败页面脚本形如：Msg=01;time='...';msga='ldap auth error';
+        let ret_code = extract_script_value(text, "Msg=")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default();
+        let msga = extract_script_value(text, "msga=").unwrap_or_default();
+
+        let response = DrcomLoginResponse {
+            result: 0,
+            ret_code,
+            msga,
+            ..Default::default()
+        };
+
+        PortalLoginOutcome {
+            success: false,
+            failure_reason: response.failure_reason(),
+            response: Some(response),
+        }
+    }
+}
+
+/// 读取页面脚本中`key`之后的值，去掉引号，遇到`;`或换行结束
+fn extract_script_value(text: &str, key: &str) -> Option<String> {
+    let start = text.find(key)? + key.len();
+    let value = text[start..]
+        .split([';', '\n'])
+        .next()?
+        .trim()
+        .trim_matches(['\'', '"']);
+
+    Some(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::drcom::LoginFailureReason;
+
+    #[test]
+    fn test_parse_eportal_login_page() {
+        let driver = EportalDriver::new(NetworkConfig::default());
+
+        let success =
+            driver.parse_login_response("<html><head><title>认证成功页</title></head></html>");
+        assert!(success.success);
+
+        let failure = driver.parse_login_response(
+            "<script>Msg=01;time='2025-01-01 08:00:00';flow='0';msga='ldap auth error';</script>",
+        );
+        assert!(!failure.success);
+        assert_eq!(
+            failure.failure_reason,
+            Some(LoginFailureReason::WrongPassword)
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! GUET Dr.COM认证驱动
+//!
+//! 通过`/drcom/login`和`/drcom/logout`接口登录注销，接口返回`dr1003(...)`形式的JSONP
+
+use super::{
+    DEFAULT_USER_AGENT, PortalDriver, PortalLoginOutcome, fetch_portal_page, is_signed_out,
+    page_status, portal_url, read_response_text,
+};
+use crate::core::drcom::DrcomLoginResponse;
+use crate::core::error::{AppError, AppResult};
+use crate::core::message::CampusNetworkStatus;
+use crate::core::network::{NetworkConfig, is_login_successful};
+use crate::core::secret_string::SecretString;
+use async_trait::async_trait;
+use reqwest::Client;
+use urlencoding::encode;
+use zeroize::Zeroizing;
+
+/// 默认登录接口路径
+const DEFAULT_LOGIN_PATH: &str = "/drcom/login";
+
+/// GUET Dr.COM认证驱动
+#[derive(Debug, Clone)]
+pub struct GuetDrcomDriver {
+    config: NetworkConfig,
+}
+
+impl GuetDrcomDriver {
+    /// 创建新的驱动实例
+    pub fn new(config: NetworkConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl PortalDriver for GuetDrcomDriver {
+    fn name(&self) -> &'static str {
+        "guet_drcom"
+    }
+
+    async fn check_status(&self, client: &Client) -> AppResult<CampusNetworkStatus> {
+        let page = fetch_portal_page(client, &self.config).await?;
+        Ok(page_status(&page, &self.config))
+    }
+
+    async fn login(
+        &self,
+        client: &Client,
+        username: &str,
+        password: &SecretString,
+        isp: &str,
+    ) -> AppResult<PortalLoginOutcome> {
+        let portal = &self.config.portal;
+
+        // 构造URL参数，密码需要URL编码；包含密码的URL在请求结束后清零
+        let params = Zeroizing::new(format!(
+            "callback={}&DDDDD={}&upass={}&0MKKey={}",
+            encode(&portal.callback),
+            portal.full_username(username, isp),
+            Zeroizing::new(encode(password.expose()).into_owned()).as_str(),
+            portal.mkkey
+        ));
+        let path = portal.login_path.as_deref().unwrap_or(DEFAULT_LOGIN_PATH);
+        let url = Zeroizing::new(format!(
+            "{}?{}",
+            portal_url(&self.config.login_ip, path),
+            params.as_str()
+        ));
+
+        let response = client
+            .get(url.as_str())
+            .header("User-Agent", DEFAULT_USER_AGENT)
+            .header("Referer", &self.config.login_ip)
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: crate::core::error::map_reqwest_error(e),
+            })?;
+
+        let text = read_response_text(response, 8192).await?;
+        Ok(self.parse_login_response(&text))
+    }
+
+    async fn logout(&self, client: &Client) -> AppResult<bool> {
+        let url = portal_url(&self.config.login_ip, "/drcom/logout?callback=dr1004");
+
+        client
+            .get(&url)
+            .header("User-Agent", DEFAULT_USER_AGENT)
+            .header("Referer", &self.config.login_ip)
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: crate::core::error::map_reqwest_error(e),
+            })?;
+
+        let page = fetch_portal_page(client, &self.config).await?;
+        Ok(is_signed_out(&page, &self.config))
+    }
+
+    fn parse_login_response(&self, text: &str) -> PortalLoginOutcome {
+        match DrcomLoginResponse::parse(text) {
+            Some(response) => PortalLoginOutcome {
+                success: response.is_success(),
+                failure_reason: response.failure_reason(),
+                response: Some(response),
+            },
+            // 旧版认证系统返回HTML页面而不是JSONP，此时退回到关键字判断
+            None => PortalLoginOutcome {
+                success: is_login_successful(text),
+                failure_reason: None,
+                response: None,
+            },
+        }
+    }
+}
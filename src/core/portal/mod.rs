@@ -0,0 +1,189 @@
+//! 认证系统驱动模块
+//!
+//! 不同学校、不同版本的认证系统在登录地址、参数和响应格式上各不相同，
+//! 每种认证系统由一个`PortalDriver`实现，`NetworkManager`只负责调度
+//!
+//! 目前提供两种驱动：
+//! - `guet_drcom`：GUET使用的Dr.COM JSONP接口（`/drcom/login?callback=dr1003&...`）
+//! - `eportal`：通用Dr.COM网页认证表单（POST提交`DDDDD`/`upass`，返回HTML页面）
+
+mod eportal;
+mod guet_drcom;
+
+pub use eportal::EportalDriver;
+pub use guet_drcom::GuetDrcomDriver;
+
+use crate::core::drcom::{DrcomLoginResponse, LoginFailureReason};
+use crate::core::error::{AppError, AppResult, NetworkError};
+use crate::core::message::CampusNetworkStatus;
+use crate::core::network::NetworkConfig;
+use crate::core::secret_string::SecretString;
+use async_trait::async_trait;
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// 限制响应体大小为10MB（对于校园网认证足够大，同时防止极端情况）
+const MAX_RESPONSE_SIZE: usize = 10 * 1024 * 1024;
+
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/139.0.0.0 Safari/537.36";
+
+/// 认证系统驱动类型
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PortalDriverKind {
+    /// GUET Dr.COM JSONP接口
+    #[default]
+    GuetDrcom,
+    /// 通用Dr.COM网页认证表单
+    Eportal,
+}
+
+/// 认证系统配置
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PortalConfig {
+    /// 使用的驱动
+    pub driver: PortalDriverKind,
+    /// 登录接口路径，未设置时使用驱动的默认路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login_path: Option<String>,
+    /// 登录表单中的`0MKKey`参数
+    pub mkkey: String,
+    /// 账号与运营商之间的分隔符，如`学号@cmcc`中的`@`
+    pub isp_separator: String,
+    /// 登录请求中JSONP回调函数的名称（`callback`参数）
+    pub callback: String,
+}
+
+impl Default for PortalConfig {
+    fn default() -> Self {
+        Self {
+            driver: PortalDriverKind::default(),
+            login_path: None,
+            mkkey: "123456".to_string(),
+            isp_separator: "@".to_string(),
+            callback: "dr1003".to_string(),
+        }
+    }
+}
+
+impl PortalConfig {
+    /// 拼接带运营商后缀的登录账号
+    pub fn full_username(&self, username: &str, isp: &str) -> String {
+        if isp.is_empty() {
+            username.to_string()
+        } else {
+            format!("{}{}{}", username, self.isp_separator, isp)
+        }
+    }
+}
+
+/// 认证系统的登录响应
+#[derive(Debug, Clone, Default)]
+pub struct PortalLoginOutcome {
+    /// 是否登录成功
+    pub success: bool,
+    /// 登录失败原因
+    pub failure_reason: Option<LoginFailureReason>,
+    /// 解析后的认证服务器响应
+    pub response: Option<DrcomLoginResponse>,
+}
+
+/// 认证系统驱动
+#[async_trait]
+pub trait PortalDriver: Debug + Send + Sync {
+    /// 驱动名称
+    fn name(&self) -> &'static str;
+
+    /// 检查校园网登录状态
+    async fn check_status(&self, client: &Client) -> AppResult<CampusNetworkStatus>;
+
+    /// 发送登录请求并解析响应
+    ///
+    /// `isp`为已规范化的运营商标识，校园网运营商为空字符串
+    async fn login(
+        &self,
+        client: &Client,
+        username: &str,
+        password: &SecretString,
+        isp: &str,
+    ) -> AppResult<PortalLoginOutcome>;
+
+    /// 发送注销请求，返回认证页面是否已回到未登录状态
+    async fn logout(&self, client: &Client) -> AppResult<bool>;
+
+    /// 解析登录响应内容
+    fn parse_login_response(&self, text: &str) -> PortalLoginOutcome;
+}
+
+/// 根据网络配置创建认证系统驱动
+pub fn create_driver(config: &NetworkConfig) -> Arc<dyn PortalDriver> {
+    match config.portal.driver {
+        PortalDriverKind::GuetDrcom => Arc::new(GuetDrcomDriver::new(config.clone())),
+        PortalDriverKind::Eportal => Arc::new(EportalDriver::new(config.clone())),
+    }
+}
+
+/// 拼接认证地址与路径
+fn portal_url(login_ip: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        login_ip.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// 获取认证页面内容（截取前4KB，足以包含页面标题）
+async fn fetch_portal_page(client: &Client, config: &NetworkConfig) -> AppResult<String> {
+    let response =
+        client
+            .get(&config.login_ip)
+            .send()
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: crate::core::error::map_reqwest_error(e),
+            })?;
+
+    read_response_text(response, 4096).await
+}
+
+/// 根据认证页面标题判断登录状态
+fn page_status(page: &str, config: &NetworkConfig) -> CampusNetworkStatus {
+    if page.contains(&config.signed_in_title) {
+        CampusNetworkStatus::AlreadyLoggedIn
+    } else {
+        // 未登录页面和无法识别的页面都视为未登录
+        CampusNetworkStatus::NotLoggedIn
+    }
+}
+
+/// 认证页面是否已回到未登录状态
+fn is_signed_out(page: &str, config: &NetworkConfig) -> bool {
+    !page.contains(&config.signed_in_title) && page.contains(&config.not_sign_in_title)
+}
+
+/// 读取响应内容，拒绝过大的响应并截取前`limit`字节
+async fn read_response_text(response: Response, limit: usize) -> AppResult<String> {
+    let content_length = response.content_length().unwrap_or(0) as usize;
+    if content_length > MAX_RESPONSE_SIZE {
+        return Err(AppError::NetworkError {
+            source: NetworkError::Other("响应体过大".to_string()),
+        });
+    }
+
+    let text = response.text().await.map_err(|e| AppError::NetworkError {
+        source: NetworkError::Other(e.to_string()),
+    })?;
+
+    if text.len() <= limit {
+        return Ok(text);
+    }
+
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Ok(text[..end].to_string())
+}
@@ -0,0 +1,204 @@
+//! 广域网探测模块
+//!
+//! 定义`[network.probes]`配置中的探测目标，并执行单个目标的连通性探测
+//!
+//! 支持三种探测方式：
+//! - `http`：请求URL并校验状态码或响应内容，适合`generate_204`类端点
+//! - `tcp`：仅建立TCP连接（认证系统劫持TCP时未认证也能连接成功）
+//! - `dns`：仅解析域名（认证前通常也能解析，只能说明DNS可用）
+//!
+//! 任一目标成功即视为已接入广域网，因此默认只使用能识别认证页面的`generate_204`端点
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 广域网探测配置
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ProbesConfig {
+    /// 单个探测的超时时间（秒）
+    pub timeout_secs: u64,
+    /// 探测目标，任一目标成功即视为已接入广域网
+    pub targets: Vec<ProbeTarget>,
+}
+
+impl Default for ProbesConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 5,
+            targets: vec![
+                ProbeTarget::Http {
+                    url: "http://connect.rom.miui.com/generate_204".to_string(),
+                    expect_status: Some(204),
+                    expect_body: None,
+                },
+                ProbeTarget::Http {
+                    url: "http://wifi.vivo.com.cn/generate_204".to_string(),
+                    expect_status: Some(204),
+                    expect_body: None,
+                },
+                ProbeTarget::Http {
+                    url: "http://www.gstatic.cn/generate_204".to_string(),
+                    expect_status: Some(204),
+                    expect_body: None,
+                },
+            ],
+        }
+    }
+}
+
+/// 探测目标
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ProbeTarget {
+    /// HTTP探测
+    Http {
+        /// 请求地址
+        url: String,
+        /// 期望的状态码，未设置时接受任意2xx状态码
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expect_status: Option<u16>,
+        /// 响应内容中期望包含的文本
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expect_body: Option<String>,
+    },
+    /// TCP连接探测
+    Tcp {
+        /// 连接地址，格式为`host:port`
+        address: String,
+    },
+    /// DNS解析探测
+    Dns {
+        /// 解析的域名
+        host: String,
+    },
+}
+
+impl ProbeTarget {
+    /// 探测目标的描述，用于日志和状态输出
+    pub fn describe(&self) -> String {
+        match self {
+            ProbeTarget::Http { url, .. } => format!("HTTP {}", url),
+            ProbeTarget::Tcp { address } => format!("TCP {}", address),
+            ProbeTarget::Dns { host } => format!("DNS {}", host),
+        }
+    }
+
+    /// 执行探测，返回目标是否可访问
+    ///
+    /// `login_ip`和`not_sign_in_title`用于识别被认证系统劫持的HTTP响应
+    pub async fn probe(
+        &self,
+        client: &Client,
+        timeout: Duration,
+        login_ip: &str,
+        not_sign_in_title: &str,
+    ) -> bool {
+        let probe = async {
+            match self {
+                ProbeTarget::Http {
+                    url,
+                    expect_status,
+                    expect_body,
+                } => {
+                    probe_http(
+                        client,
+                        url,
+                        *expect_status,
+                        expect_body.as_deref(),
+                        login_ip,
+                        not_sign_in_title,
+                    )
+                    .await
+                }
+                ProbeTarget::Tcp { address } => tokio::net::TcpStream::connect(address.as_str())
+                    .await
+                    .is_ok(),
+                ProbeTarget::Dns { host } => tokio::net::lookup_host(format!("{}:0", host))
+                    .await
+                    .map(|mut addrs| addrs.next().is_some())
+                    .unwrap_or(false),
+            }
+        };
+
+        tokio::time::timeout(timeout, probe).await.unwrap_or(false)
+    }
+}
+
+/// HTTP探测
+///
+/// 认证系统会把未登录设备的请求重定向或直接替换为认证页面，
+/// 因此`generate_204`端点只有在返回204时才说明真正接入了广域网
+async fn probe_http(
+    client: &Client,
+    url: &str,
+    expect_status: Option<u16>,
+    expect_body: Option<&str>,
+    login_ip: &str,
+    not_sign_in_title: &str,
+) -> bool {
+    let Ok(response) = client.get(url).send().await else {
+        return false;
+    };
+
+    // 检查是否被重定向到校园网登录页面
+    if let Some(host) = response.url().host_str()
+        && login_ip.contains(host)
+    {
+        return false;
+    }
+
+    let status = response.status();
+    let status_matched = match expect_status {
+        Some(expected) => status.as_u16() == expected,
+        None => status.is_success(),
+    };
+    if !status_matched {
+        return false;
+    }
+
+    // 204端点没有响应内容，无需下载
+    if status.as_u16() == 204 && expect_body.is_none() {
+        return true;
+    }
+
+    let Ok(text) = response.text().await else {
+        return false;
+    };
+
+    // 响应内容是登录页面，说明未连接外网
+    if text.contains(not_sign_in_title) {
+        return false;
+    }
+
+    expect_body.is_none_or(|body| text.contains(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_targets_round_trip() {
+        let config = ProbesConfig::default();
+        let content = toml::to_string(&config).unwrap();
+        let parsed: ProbesConfig = toml::from_str(&content).unwrap();
+        assert_eq!(parsed, config);
+
+        let parsed: ProbesConfig = toml::from_str(
+            r#"targets = [{ kind = "dns", host = "www.guet.edu.cn" }, { kind = "http", url = "https://www.baidu.com", expect_body = "baidu" }]"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.timeout_secs, 5);
+        assert_eq!(parsed.targets[0].describe(), "DNS www.guet.edu.cn");
+        assert_eq!(
+            parsed.targets[1],
+            ProbeTarget::Http {
+                url: "https://www.baidu.com".to_string(),
+                expect_status: None,
+                expect_body: Some("baidu".to_string()),
+            }
+        );
+    }
+}
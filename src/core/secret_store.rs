@@ -0,0 +1,438 @@
+//! 密码存储模块
+//!
+//! 登录时通过`SecretStore`读取账号的密码，由`[secret]`中的`backend`选择存储后端：
+//! - `config`：默认，密码加密后保存在配置文件的`encrypted_password`中
+//! - `keyring`：系统密钥环（Windows凭据管理器、macOS钥匙串、Linux Secret Service）
+//! - `command`：执行外部命令读取密码，如`pass show guet`，使用输出的第一行
+//!
+//! 使用`keyring`和`command`后端时`encrypted_password`可以留空
+
+use crate::core::config::{AccountConfig, ConfigData};
+use crate::core::crypto::{
+    CiphertextFormat, decrypt_config_password, encrypt_password_as, encrypt_password_like,
+};
+use crate::core::error::{AppError, AppResult};
+use crate::core::passphrase::session_passphrase;
+use crate::core::secret_string::SecretString;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+/// 系统密钥环中使用的服务名
+pub const KEYRING_SERVICE: &str = "AutoLoginGUET";
+
+/// 外部命令中替换为账号的占位符
+pub const COMMAND_USERNAME_PLACEHOLDER: &str = "{username}";
+
+/// 执行外部命令时传入账号的环境变量
+pub const COMMAND_USERNAME_ENV: &str = "AUTOLOGINGUET_USERNAME";
+
+/// shell中引用账号环境变量的写法，由shell展开，账号中的特殊字符不会被当作命令执行
+#[cfg(windows)]
+const COMMAND_USERNAME_REFERENCE: &str = "!AUTOLOGINGUET_USERNAME!";
+#[cfg(not(windows))]
+const COMMAND_USERNAME_REFERENCE: &str = "\"$AUTOLOGINGUET_USERNAME\"";
+
+/// 密码存储后端
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretBackend {
+    /// 加密保存在配置文件中
+    #[default]
+    Config,
+    /// 系统密钥环
+    Keyring,
+    /// 外部命令
+    Command,
+}
+
+impl SecretBackend {
+    /// 转换为消息文本
+    pub fn to_message(&self) -> &'static str {
+        match self {
+            SecretBackend::Config => "配置文件",
+            SecretBackend::Keyring => "系统密钥环",
+            SecretBackend::Command => "外部命令",
+        }
+    }
+}
+
+/// 密码存储配置
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct SecretConfig {
+    /// 存储后端
+    pub backend: SecretBackend,
+    /// `command`后端执行的命令，`{username}`会替换为账号（不需要再加引号）
+    pub command: String,
+}
+
+impl SecretConfig {
+    /// 账号是否有可用的密码
+    ///
+    /// `config`后端要求`encrypted_password`不为空，其他后端在读取时才能确定
+    pub fn has_password(&self, account_encrypted_password: &str) -> bool {
+        self.backend != SecretBackend::Config || !account_encrypted_password.is_empty()
+    }
+}
+
+/// 密码存储
+pub trait SecretStore: Send + Sync {
+    /// 后端名称，用于错误信息
+    fn name(&self) -> String;
+
+    /// 读取账号的密码
+    fn get_password(&self, account: &AccountConfig) -> AppResult<SecretString>;
+
+    /// 保存账号的密码，需要写入配置文件的内容更新到`account`中
+    fn set_password(&self, account: &mut AccountConfig, password: &str) -> AppResult<()>;
+}
+
+/// 密码存储中账号对应的条目名
+///
+/// 同一学号的校园网账号和运营商账号密码不同，运营商账号使用`账号@运营商`，
+/// 校园网账号仍使用账号，与旧版本保存的条目一致
+pub fn secret_entry_name(account: &AccountConfig) -> String {
+    match account.isp.trim_start_matches('@') {
+        "" => account.username.clone(),
+        isp => format!("{}@{}", account.username, isp),
+    }
+}
+
+/// 根据配置创建密码存储
+pub fn secret_store_from_config(config: &SecretConfig) -> Arc<dyn SecretStore> {
+    match config.backend {
+        SecretBackend::Config => Arc::new(ConfigSecretStore::default()),
+        SecretBackend::Keyring => Arc::new(KeyringSecretStore::new(KEYRING_SERVICE)),
+        SecretBackend::Command => Arc::new(CommandSecretStore::new(&config.command)),
+    }
+}
+
+/// 为还没有保存密码的新账号创建密码存储
+///
+/// `config`后端按配置中已保存密码的格式加密，机器密钥和主密码不会混用
+pub fn secret_store_for_new_account(config: &ConfigData) -> Arc<dyn SecretStore> {
+    match config.secret.backend {
+        SecretBackend::Config => Arc::new(ConfigSecretStore::with_format(config.password_format())),
+        _ => secret_store_from_config(&config.secret),
+    }
+}
+
+/// 读取密码失败时的错误，`user_msg`会显示给用户
+fn password_unavailable(internal_msg: String, user_msg: String) -> AppError {
+    AppError::PasswordDecryptionError {
+        internal_msg,
+        user_msg,
+    }
+}
+
+/// 加密保存在配置文件中的密码
+///
+/// 未指定格式时按账号已有密文的格式加密新密码
+#[derive(Default)]
+pub struct ConfigSecretStore {
+    format: Option<CiphertextFormat>,
+}
+
+impl ConfigSecretStore {
+    /// 使用指定的密文格式加密新密码
+    pub fn with_format(format: CiphertextFormat) -> Self {
+        Self {
+            format: Some(format),
+        }
+    }
+}
+
+impl SecretStore for ConfigSecretStore {
+    fn name(&self) -> String {
+        SecretBackend::Config.to_message().to_string()
+    }
+
+    fn get_password(&self, account: &AccountConfig) -> AppResult<SecretString> {
+        decrypt_config_password(&account.encrypted_password)
+    }
+
+    fn set_password(&self, account: &mut AccountConfig, password: &str) -> AppResult<()> {
+        account.encrypted_password = match self.format {
+            Some(format) => encrypt_password_as(password, format, session_passphrase().as_ref())?,
+            None => encrypt_password_like(password, &account.encrypted_password)?,
+        };
+        Ok(())
+    }
+}
+
+/// 系统密钥环，每个账号和运营商对应一个条目
+pub struct KeyringSecretStore {
+    service: String,
+}
+
+impl KeyringSecretStore {
+    /// 使用指定服务名的条目
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    /// 指定名称的密钥环条目
+    fn entry(&self, name: &str) -> AppResult<keyring::Entry> {
+        keyring::Entry::new(&self.service, name)
+            .map_err(|e| AppError::SystemError(format!("无法访问系统密钥环: {}", e)))
+    }
+
+    /// 迁移旧版本以账号为名保存的运营商账号密码
+    ///
+    /// 旧版本不区分运营商，条目中保存的是该账号最后一次写入的密码，
+    /// 复制到新条目后保留旧条目，同一学号的校园网账号仍在使用
+    fn migrate_legacy_entry(&self, account: &AccountConfig) -> Option<String> {
+        let name = secret_entry_name(account);
+        if name == account.username {
+            return None;
+        }
+
+        let password = self.entry(&account.username).ok()?.get_password().ok()?;
+        if let Ok(entry) = self.entry(&name) {
+            let _ = entry.set_password(&password);
+        }
+        Some(password)
+    }
+}
+
+impl SecretStore for KeyringSecretStore {
+    fn name(&self) -> String {
+        SecretBackend::Keyring.to_message().to_string()
+    }
+
+    fn get_password(&self, account: &AccountConfig) -> AppResult<SecretString> {
+        let name = secret_entry_name(account);
+        let password = match self.entry(&name)?.get_password() {
+            Err(keyring::Error::NoEntry) => self
+                .migrate_legacy_entry(account)
+                .ok_or(keyring::Error::NoEntry),
+            result => result,
+        };
+
+        password.map(SecretString::new).map_err(|e| match e {
+            keyring::Error::NoEntry => password_unavailable(
+                format!("系统密钥环中没有{}的条目", name),
+                format!("系统密钥环中没有账号{}的密码", name),
+            ),
+            e => password_unavailable(
+                format!("读取系统密钥环失败: {}", e),
+                "无法读取系统密钥环中的密码".to_string(),
+            ),
+        })
+    }
+
+    fn set_password(&self, account: &mut AccountConfig, password: &str) -> AppResult<()> {
+        self.entry(&secret_entry_name(account))?
+            .set_password(password)
+            .map_err(|e| AppError::SystemError(format!("写入系统密钥环失败: {}", e)))?;
+
+        // 密码只保存在密钥环中
+        account.encrypted_password.clear();
+        Ok(())
+    }
+}
+
+/// 执行外部命令读取密码，如`pass show guet`
+pub struct CommandSecretStore {
+    command: String,
+}
+
+impl CommandSecretStore {
+    /// 使用指定的命令，`{username}`会替换为账号（不需要再加引号）
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+
+    /// 替换占位符后的命令
+    ///
+    /// 账号通过环境变量传入，占位符替换为对环境变量的引用，不直接拼接到命令中
+    fn command_for(&self) -> String {
+        self.command
+            .replace(COMMAND_USERNAME_PLACEHOLDER, COMMAND_USERNAME_REFERENCE)
+    }
+}
+
+impl SecretStore for CommandSecretStore {
+    fn name(&self) -> String {
+        SecretBackend::Command.to_message().to_string()
+    }
+
+    fn get_password(&self, account: &AccountConfig) -> AppResult<SecretString> {
+        if self.command.trim().is_empty() {
+            return Err(AppError::ConfigError(
+                "未配置读取密码的命令（secret.command）".to_string(),
+            ));
+        }
+
+        let command = self.command_for();
+        let output = shell_command(&command)
+            .env(COMMAND_USERNAME_ENV, &account.username)
+            .output()
+            .map_err(|e| {
+                password_unavailable(
+                    format!("无法执行命令 '{}': {}", command, e),
+                    "无法执行读取密码的命令".to_string(),
+                )
+            })?;
+        let stdout = Zeroizing::new(output.stdout);
+
+        if !output.status.success() {
+            return Err(password_unavailable(
+                format!(
+                    "命令 '{}' 执行失败（{}）: {}",
+                    command,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                "读取密码的命令执行失败".to_string(),
+            ));
+        }
+
+        let stdout = Zeroizing::new(String::from_utf8_lossy(&stdout).into_owned());
+        match stdout.lines().next() {
+            Some(password) if !password.is_empty() => Ok(SecretString::from(password)),
+            _ => Err(password_unavailable(
+                format!("命令 '{}' 没有输出密码", command),
+                "读取密码的命令没有输出密码".to_string(),
+            )),
+        }
+    }
+
+    fn set_password(&self, _account: &mut AccountConfig, _password: &str) -> AppResult<()> {
+        Err(AppError::ConfigError(
+            "外部命令只能读取密码，请在密码管理器中修改".to_string(),
+        ))
+    }
+}
+
+/// 通过系统shell执行命令，支持管道和参数
+///
+/// 启用延迟展开，`!AUTOLOGINGUET_USERNAME!`在解析命令之后才展开
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/V:ON", "/C", command]);
+    cmd
+}
+
+/// 通过系统shell执行命令，支持管道和参数
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+/// 保存在内存中的密码，用于测试
+#[derive(Default)]
+pub struct MemorySecretStore {
+    passwords: Mutex<HashMap<String, SecretString>>,
+}
+
+impl MemorySecretStore {
+    /// 创建空的内存存储
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn name(&self) -> String {
+        "内存".to_string()
+    }
+
+    fn get_password(&self, account: &AccountConfig) -> AppResult<SecretString> {
+        self.passwords
+            .lock()
+            .unwrap()
+            .get(&secret_entry_name(account))
+            .cloned()
+            .ok_or_else(|| {
+                password_unavailable(
+                    format!("内存中没有{}的密码", secret_entry_name(account)),
+                    format!("没有账号{}的密码", secret_entry_name(account)),
+                )
+            })
+    }
+
+    fn set_password(&self, account: &mut AccountConfig, password: &str) -> AppResult<()> {
+        self.passwords
+            .lock()
+            .unwrap()
+            .insert(secret_entry_name(account), SecretString::from(password));
+        account.encrypted_password.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_secret_store_separates_isp_accounts() {
+        let store = MemorySecretStore::new();
+        let mut campus = AccountConfig {
+            username: "2100000000".to_string(),
+            ..Default::default()
+        };
+        let mut isp = AccountConfig {
+            isp: "@cmcc".to_string(),
+            ..campus.clone()
+        };
+
+        store.set_password(&mut campus, "campus password").unwrap();
+        store.set_password(&mut isp, "isp password").unwrap();
+        assert_eq!(
+            store.get_password(&campus).unwrap().expose(),
+            "campus password"
+        );
+        assert_eq!(store.get_password(&isp).unwrap().expose(), "isp password");
+        assert_eq!(secret_entry_name(&campus), "2100000000");
+        assert_eq!(secret_entry_name(&isp), "2100000000@cmcc");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_secret_store_reads_first_line() {
+        let account = AccountConfig {
+            username: "2100000000".to_string(),
+            ..Default::default()
+        };
+
+        let store = CommandSecretStore::new("printf 'pw-%s\\nurl: guet\\n' {username}");
+        assert_eq!(
+            store.get_password(&account).unwrap().expose(),
+            "pw-2100000000"
+        );
+
+        // 账号中的shell特殊字符不会被执行
+        let account = AccountConfig {
+            username: "$(echo injected); echo injected".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.get_password(&account).unwrap().expose(),
+            "pw-$(echo injected); echo injected"
+        );
+
+        assert!(
+            CommandSecretStore::new("exit 1")
+                .get_password(&account)
+                .is_err()
+        );
+        assert!(
+            CommandSecretStore::new("true")
+                .get_password(&account)
+                .is_err()
+        );
+        assert!(CommandSecretStore::new("").get_password(&account).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! 敏感字符串模块
+//!
+//! 明文密码使用`SecretString`传递，离开作用域时清零内存，
+//! 不实现`Display`，`Debug`只输出占位文本，避免密码被写入日志或崩溃信息
+
+use std::fmt;
+use zeroize::Zeroize;
+
+/// 释放时清零内存的字符串
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// 包装明文字符串，之后由`SecretString`负责清零
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    /// 读取明文，只应在构造请求或加密时使用，不要复制到普通的`String`中长期保存
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_string())
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 类型实现了`Display`时调用固有方法，否则退回到`NotDisplay`的默认实现
+    struct DisplayProbe<T>(T);
+
+    impl<T: fmt::Display> DisplayProbe<T> {
+        fn is_display(&self) -> bool {
+            true
+        }
+    }
+
+    trait NotDisplay {
+        fn is_display(&self) -> bool {
+            false
+        }
+    }
+
+    impl<T> NotDisplay for DisplayProbe<T> {}
+
+    #[test]
+    fn test_secret_string_is_redacted() {
+        let secret = SecretString::from("p@ss word");
+        assert_eq!(secret.expose(), "p@ss word");
+        assert_eq!(format!("{:?}", secret), "SecretString(***)");
+        assert_eq!(
+            format!("{:?}", Some(secret.clone())),
+            "Some(SecretString(***))"
+        );
+
+        assert!(!DisplayProbe(secret).is_display());
+        assert!(DisplayProbe(String::new()).is_display());
+    }
+}
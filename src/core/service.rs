@@ -2,15 +2,22 @@
 //!
 //! 封装了所有的业务逻辑
 
-use crate::core::config::{ConfigData, is_config_complete, load_config, save_config};
-use crate::core::crypto::decrypt_password_with_machine_key;
-use crate::core::error::{AppError, AppResult};
+use crate::core::config::{
+    AccountConfig, ConfigData, is_config_complete, load_config_with_report, save_config,
+};
+use crate::core::error::{AppError, AppResult, NetworkError};
 use crate::core::events::{
-    EventBus, EventHandler, notify_auto_start_set, notify_config_saved, notify_login_attempted,
+    EventBus, EventHandler, notify_auto_start_set, notify_config_loaded, notify_config_saved,
+    notify_login_attempted, notify_logout_completed,
 };
-use crate::core::flow::FlowService;
+use crate::core::flow::{FlowService, OnlineDevice, SelfServiceAccountInfo};
+use crate::core::flow_history::{FLOW_HISTORY_FILE_NAME, FlowForecast, FlowHistory};
+use crate::core::flow_switch::FlowSwitchDecision;
 use crate::core::message::{CampusNetworkStatus, MessageCenter, WanStatus};
 use crate::core::network::{NetworkManager, NetworkManagerTrait};
+use crate::core::paths::state_file_path;
+use crate::core::secret_store::{SecretStore, secret_store_from_config};
+use crate::core::secret_string::SecretString;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -68,6 +75,17 @@ pub struct LoginResult {
     pub elapsed_time: f64,
 }
 
+/// 注销结果
+#[derive(Debug, Clone)]
+pub struct LogoutResult {
+    /// 注销是否成功
+    pub success: bool,
+    /// 注销消息
+    pub message: String,
+    /// 注销耗时（秒）
+    pub elapsed_time: f64,
+}
+
 /// 认证服务
 #[derive(Clone)]
 pub struct AuthService {
@@ -75,6 +93,8 @@ pub struct AuthService {
     message_center: MessageCenter,
     event_bus: EventBus,
     flow_service: FlowService,
+    /// 读取账号密码的存储后端
+    secret_store: Arc<dyn SecretStore>,
     /// 程序启动时间（计算从程序启动到完成操作的总时间）
     startup_time: Option<Instant>,
 }
@@ -92,21 +112,33 @@ impl AuthService {
         if let Ok(config) = self.load_config()
             && config.account.isp.is_empty()
         {
-            // 检查消息配置中是否包含%4占位符
-            return config.message.notify_text.contains("%4")
-                || config.message.gui_text.contains("%4")
-                || config.message.log_text.contains("%4");
+            // 启用流量提醒或消息模板需要账户信息
+            return config.flow_alert.enabled || config.message.needs().account_info;
         }
         false
     }
 
+    /// 查询自助服务系统的账户信息，查询成功时检查流量提醒
+    async fn query_account_info(
+        &self,
+        username: &str,
+        password: &SecretString,
+    ) -> Option<SelfServiceAccountInfo> {
+        let info = self
+            .flow_service
+            .get_account_info(username, password)
+            .await
+            .ok()?;
+
+        let _ = self.message_center.check_flow_alert(info.left_flow);
+        Some(info)
+    }
+
     /// 检查是否需要检查广域网状态
     fn should_check_wan(&self) -> bool {
         if let Ok(config) = self.load_config() {
-            // 检查消息配置中是否包含%2占位符
-            return config.message.notify_text.contains("%2")
-                || config.message.gui_text.contains("%2")
-                || config.message.log_text.contains("%2");
+            // 消息模板需要广域网状态
+            return config.message.needs().wan;
         }
         false
     }
@@ -118,17 +150,30 @@ impl AuthService {
         let event_bus = EventBus::new();
 
         let message_center = MessageCenter::new(Some(config.clone()), event_bus.clone());
-        let flow_service = FlowService::new();
+        let flow_service = FlowService::new()
+            .with_base_url(&config.network.self_service_url)
+            .with_history(FlowHistory::new(state_file_path(FLOW_HISTORY_FILE_NAME)));
 
         Self {
             network_manager: Arc::new(network_manager),
             message_center,
             event_bus,
             flow_service,
+            secret_store: secret_store_from_config(&config.secret),
             startup_time,
         }
     }
 
+    /// 替换读取账号密码的存储后端
+    pub fn set_secret_store(&mut self, secret_store: Arc<dyn SecretStore>) {
+        self.secret_store = secret_store;
+    }
+
+    /// 从存储后端读取账号的密码
+    pub fn get_password(&self, account: &AccountConfig) -> AppResult<SecretString> {
+        self.secret_store.get_password(account)
+    }
+
     /// 设置事件处理器
     pub fn set_event_handler(&mut self, handler: Box<dyn EventHandler>) {
         self.event_bus.register_handler(handler);
@@ -144,6 +189,16 @@ impl AuthService {
         &self.message_center
     }
 
+    /// 检查校园网状态，不生成消息
+    pub async fn check_campus_network(&self) -> AppResult<CampusNetworkStatus> {
+        self.network_manager.check_campus_network().await
+    }
+
+    /// 检查广域网状态，不生成消息
+    pub async fn check_wan_network(&self) -> WanStatus {
+        self.network_manager.check_wan_network().await
+    }
+
     /// 检查网络状态
     ///
     /// `show_notification`: 是否显示通知
@@ -173,24 +228,16 @@ impl AuthService {
         });
 
         // 获取流量信息（如果需要）
-        let flow_info = if self.should_get_flow_info() {
+        let account_info = if self.should_get_flow_info() {
             // 加载配置以获取账号密码
             if let Ok(config) = self.load_config() {
                 // 只有当配置完整时才获取流量信息
-                if !config.account.username.is_empty()
-                    && !config.account.encrypted_password.is_empty()
-                {
-                    match self
-                        .flow_service
-                        .get_user_flow_info(
-                            &config.account.username,
-                            &decrypt_password_with_machine_key(&config.account.encrypted_password)?,
-                        )
-                        .await
-                    {
-                        Ok(flow) => Some(flow.left_flow),
-                        Err(_) => None,
-                    }
+                if is_config_complete(&config) {
+                    self.query_account_info(
+                        &config.account.username,
+                        &self.get_password(&config.account)?,
+                    )
+                    .await
                 } else {
                     None
                 }
@@ -208,7 +255,7 @@ impl AuthService {
             elapsed,
             show_notification,
             false,
-            flow_info,
+            account_info.as_ref(),
         );
 
         Ok((campus_status, wan_status))
@@ -218,7 +265,7 @@ impl AuthService {
     pub async fn login_with_credentials(
         &self,
         username: &str,
-        password: &str,
+        password: &SecretString,
         isp: &str,
     ) -> AppResult<LoginResult> {
         let start_time = Instant::now();
@@ -237,25 +284,24 @@ impl AuthService {
                     WanStatus::CheckFailed
                 };
 
-                let flow_info = if self.should_get_flow_info() {
-                    match self
-                        .flow_service
-                        .get_user_flow_info(username, password)
-                        .await
-                    {
-                        Ok(flow) => Some(flow.left_flow),
-                        Err(_) => None,
-                    }
+                let account_info = if self.should_get_flow_info() {
+                    self.query_account_info(username, password).await
                 } else {
                     None
                 };
 
+                let account = AccountConfig {
+                    username: username.to_string(),
+                    isp: isp.to_string(),
+                    ..Default::default()
+                };
                 let message = self.message_center.handle_login_result(
+                    &account,
                     login_result.campus_status.clone(),
                     wan_status,
                     elapsed,
-                    login_result.success,
-                    flow_info,
+                    account_info.as_ref(),
+                    login_result.failure_reason.clone(),
                 );
 
                 let result = LoginResult {
@@ -286,6 +332,34 @@ impl AuthService {
         }
     }
 
+    /// 注销校园网
+    ///
+    /// 切换设备或运营商账号前调用，释放当前认证会话
+    pub async fn logout(&self) -> AppResult<LogoutResult> {
+        let start_time = Instant::now();
+
+        let (success, message) = match self.network_manager.logout().await {
+            Ok(logout_result) if logout_result.success => (true, "注销校园网成功".to_string()),
+            Ok(_) => (false, "注销校园网失败，认证页面仍为已登录状态".to_string()),
+            Err(e) => (false, format!("注销失败: {}", e)),
+        };
+
+        let elapsed = start_time.elapsed().as_secs_f64();
+
+        let log_level = if success { "INFO" } else { "WARNING" };
+        let _ = self
+            .message_center
+            .log_event(log_level, &format!("{} 用时{:.2}秒", message, elapsed));
+
+        notify_logout_completed(&self.event_bus, success, &message, elapsed);
+
+        Ok(LogoutResult {
+            success,
+            message,
+            elapsed_time: elapsed,
+        })
+    }
+
     /// 统一处理登录结果的函数
     ///
     /// 该函数处理登录结果，包括：
@@ -304,7 +378,177 @@ impl AuthService {
     }
 
     /// 静默登录
+    ///
+    /// 登录成功后按`[flow_switch]`配置检查是否需要切换校园网和运营商账号
     pub async fn silent_login(&self, config: ConfigData) -> AppResult<LoginResult> {
+        let result = self.login_with_fallback(config.clone()).await?;
+
+        if result.success && config.flow_switch.enabled {
+            let mut config = config;
+            if let Err(e) = self.apply_flow_switch(&mut config).await {
+                let _ = self
+                    .message_center
+                    .log_event("WARNING", &format!("流量切换检查失败: {}", e));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// 列出当前账号在认证系统中的在线设备
+    pub async fn list_online_devices(&self) -> AppResult<Vec<OnlineDevice>> {
+        let (username, password) = self.self_service_credentials()?;
+        self.flow_service
+            .list_online_devices(&username, &password)
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: NetworkError::Other(format!("获取在线设备失败: {}", e)),
+            })
+    }
+
+    /// 强制当前账号的指定会话下线
+    pub async fn kick_device(&self, session_id: &str) -> AppResult<()> {
+        let (username, password) = self.self_service_credentials()?;
+        self.flow_service
+            .kick_device(&username, &password, session_id)
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: NetworkError::Other(format!("强制下线失败: {}", e)),
+            })?;
+
+        let _ = self
+            .message_center
+            .log_event("INFO", &format!("已强制会话下线: {}", session_id));
+        Ok(())
+    }
+
+    /// 登录自助服务系统使用的账号和密码（不含运营商后缀）
+    fn self_service_credentials(&self) -> AppResult<(String, SecretString)> {
+        let config = self.load_config()?;
+        if !is_config_complete(&config) {
+            return Err(AppError::ConfigError("配置不完整".to_string()));
+        }
+
+        let password = self.get_password(&config.account)?;
+        Ok((config.account.username, password))
+    }
+
+    /// 根据流量历史计算当前账号的日均消耗和预计用完日期
+    ///
+    /// 历史记录在每次查询剩余流量成功后追加，记录不足时返回`None`
+    pub fn flow_forecast(&self) -> AppResult<Option<FlowForecast>> {
+        let config = self.load_config()?;
+        Ok(self.flow_service.flow_forecast(&config.account.username))
+    }
+
+    /// 按流量切换校园网和运营商账号
+    ///
+    /// 查询`flow_switch.campus_profile`档案的剩余流量，需要切换时注销当前账号并用目标档案登录，
+    /// 登录成功后才切换并保存默认档案，`config`会更新为切换后的配置；
+    /// 目标档案登录失败时保留原档案并重新登录原账号
+    pub async fn apply_flow_switch(
+        &self,
+        config: &mut ConfigData,
+    ) -> AppResult<FlowSwitchDecision> {
+        if !config.flow_switch.enabled {
+            return Ok(FlowSwitchDecision::Stay);
+        }
+
+        let campus = config
+            .profiles
+            .get(&config.flow_switch.campus_profile)
+            .cloned()
+            .ok_or_else(|| {
+                AppError::ConfigError(format!(
+                    "校园网账号档案不存在: {}",
+                    config.flow_switch.campus_profile
+                ))
+            })?;
+        let campus_password = self.get_password(&campus.to_account())?;
+
+        let flow_info = self
+            .flow_service
+            .get_user_flow_info(&campus.username, &campus_password)
+            .await
+            .map_err(|e| AppError::NetworkError {
+                source: NetworkError::Other(format!("获取流量信息失败: {}", e)),
+            })?;
+
+        let _ = self.message_center.check_flow_alert(flow_info.left_flow);
+
+        let decision = config
+            .flow_switch
+            .decide(&config.profiles.default, flow_info.left_flow);
+        let Some(target) = config
+            .flow_switch
+            .target_profile(decision)
+            .map(str::to_string)
+        else {
+            return Ok(decision);
+        };
+
+        let _ = self.message_center.log_event(
+            "INFO",
+            &format!(
+                "{}，{}: {}",
+                flow_info.format_flow_info(),
+                decision.to_message(),
+                target
+            ),
+        );
+
+        // 注销前先取出两个账号的密码，避免注销后无法登录任何账号
+        let mut switched = config.clone();
+        switched.switch_profile(&target)?;
+        let password = self.get_password(&switched.account)?;
+        let previous_password = self.get_password(&config.account)?;
+
+        // 先注销当前账号，认证系统同一设备只保留一个会话
+        let logout = self.logout().await?;
+        if !logout.success {
+            return Err(AppError::NetworkError {
+                source: NetworkError::Other(logout.message),
+            });
+        }
+
+        let result = self
+            .login_with_credentials(&switched.account.username, &password, &switched.account.isp)
+            .await?;
+
+        if result.success {
+            self.save_config(&switched)?;
+            *config = switched;
+
+            let message = format!("{}: {}", decision.to_message(), result.message);
+            let _ = self.message_center.show_notification("", &message);
+            return Ok(decision);
+        }
+
+        // 目标账号登录失败时保留原档案并重新登录原账号
+        let restored = self
+            .login_with_credentials(
+                &config.account.username,
+                &previous_password,
+                &config.account.isp,
+            )
+            .await?;
+
+        let message = format!(
+            "{}失败: {}，{}: {}",
+            decision.to_message(),
+            result.message,
+            config.profiles.default,
+            restored.message
+        );
+        let _ = self.message_center.show_notification("", &message);
+
+        Err(AppError::NetworkError {
+            source: NetworkError::Other(message),
+        })
+    }
+
+    /// 依次使用默认账号和备用档案登录，已登录时直接返回
+    async fn login_with_fallback(&self, config: ConfigData) -> AppResult<LoginResult> {
         // 如果有启动时间，则使用启动时间为起点；否则使用当前时间为起点
         let method_start_time = Instant::now();
         let start_time = self.startup_time.unwrap_or(method_start_time);
@@ -324,21 +568,13 @@ impl AuthService {
                 if campus_status == CampusNetworkStatus::AlreadyLoggedIn {
                     let elapsed = start_time.elapsed().as_secs_f64();
 
-                    let flow_info = if self.should_get_flow_info() {
+                    let account_info = if self.should_get_flow_info() {
                         if let Ok(config) = self.load_config() {
-                            match self
-                                .flow_service
-                                .get_user_flow_info(
-                                    &config.account.username,
-                                    &decrypt_password_with_machine_key(
-                                        &config.account.encrypted_password,
-                                    )?,
-                                )
-                                .await
-                            {
-                                Ok(flow) => Some(flow.left_flow),
-                                Err(_) => None,
-                            }
+                            self.query_account_info(
+                                &config.account.username,
+                                &self.get_password(&config.account)?,
+                            )
+                            .await
                         } else {
                             None
                         }
@@ -352,7 +588,7 @@ impl AuthService {
                         elapsed,
                         true,
                         true,
-                        flow_info,
+                        account_info.as_ref(),
                     );
 
                     let result = LoginResult {
@@ -371,7 +607,23 @@ impl AuthService {
             }
         }
 
-        if !is_config_complete(&config) {
+        self.login_with_profiles_since(&config, start_time).await
+    }
+
+    /// 依次使用默认账号和备用档案登录，不检查当前是否已登录
+    ///
+    /// 守护模式检测到未登录后调用，与静默登录使用相同的备用档案顺序
+    pub async fn login_with_profiles(&self, config: &ConfigData) -> AppResult<LoginResult> {
+        self.login_with_profiles_since(config, Instant::now()).await
+    }
+
+    /// 依次使用默认账号和备用档案登录，耗时从`start_time`开始计算
+    async fn login_with_profiles_since(
+        &self,
+        config: &ConfigData,
+        start_time: Instant,
+    ) -> AppResult<LoginResult> {
+        if !is_config_complete(config) {
             let elapsed = start_time.elapsed().as_secs_f64();
             let message = "配置不完整";
 
@@ -389,19 +641,41 @@ impl AuthService {
             return Ok(result);
         }
 
+        // 默认账号登录失败时依次尝试备用档案
+        let mut result = self.login_with_account(&config.account, start_time).await?;
+        for (name, account) in config.login_accounts().iter().skip(1) {
+            if result.success {
+                break;
+            }
+
+            let _ = self
+                .message_center
+                .log_event("WARNING", &format!("登录失败，尝试账号档案: {}", name));
+            result = self.login_with_account(account, start_time).await?;
+        }
+
+        Ok(result)
+    }
+
+    /// 使用账户配置登录，密码解密失败或请求出错时返回失败结果
+    async fn login_with_account(
+        &self,
+        account: &AccountConfig,
+        start_time: Instant,
+    ) -> AppResult<LoginResult> {
         let password = match crate::core::crypto::handle_password_decryption_error(
-            decrypt_password_with_machine_key(&config.account.encrypted_password),
+            self.get_password(account),
             &self.event_bus,
         ) {
             Ok(pwd) => pwd,
-            Err(_) => {
+            Err(e) => {
                 // 错误已经通过`handle_password_decryption_error`处理，这里只需要返回一个失败的LoginResult
                 let elapsed = start_time.elapsed().as_secs_f64();
-                let message = "密码解密失败，请重新输入密码";
+                let message = e.to_string();
 
                 let result = LoginResult {
                     success: false,
-                    message: message.to_string(),
+                    message: message.clone(),
                     elapsed_time: elapsed,
                 };
 
@@ -409,14 +683,14 @@ impl AuthService {
                 let _ = self
                     .message_center
                     .log_event("ERROR", &format!("{} 用时{:.2}秒", message, elapsed));
-                let _ = self.message_center.show_notification("", message);
+                let _ = self.message_center.show_notification("", &message);
 
                 return Ok(result);
             }
         };
 
         let result = self
-            .login_with_credentials(&config.account.username, &password, &config.account.isp)
+            .login_with_credentials(&account.username, &password, &account.isp)
             .await;
 
         match result {
@@ -453,8 +727,14 @@ impl AuthService {
     }
 
     /// 加载配置
+    ///
+    /// 配置文件被迁移或同步修改时发送配置加载事件
     pub fn load_config(&self) -> AppResult<ConfigData> {
-        load_config()
+        let (config, report) = load_config_with_report()?;
+        if report.has_changes() {
+            notify_config_loaded(&self.event_bus, true, &report.to_message());
+        }
+        Ok(config)
     }
 
     /// 保存配置
@@ -477,7 +757,6 @@ impl AuthService {
     }
 
     /// 设置开机自启
-    #[cfg(windows)]
     pub fn set_auto_start(&self, enabled: bool) -> AppResult<()> {
         set_auto_start(enabled)
             .map(|_| {
@@ -506,7 +785,7 @@ impl AuthService {
 
 #[cfg(windows)]
 fn set_auto_start(enabled: bool) -> AppResult<()> {
-    use std::env;
+    use crate::core::config::auto_start_command;
     use winreg::RegKey;
     use winreg::enums::*;
 
@@ -517,11 +796,8 @@ fn set_auto_start(enabled: bool) -> AppResult<()> {
     );
 
     if enabled {
-        let exe_path = env::current_exe()
-            .map_err(|e| AppError::SystemError(format!("获取当前可执行文件路径失败: {}", e)))?;
-
-        // 添加 -silent 参数到可执行文件路径
-        let exe_path_with_args = format!("\"{}\" -silent", exe_path.to_str().unwrap_or_default());
+        let exe_path_with_args = auto_start_command()
+            .ok_or_else(|| AppError::SystemError("获取可执行文件路径失败".to_string()))?;
 
         let reg_key = hkcu
             .open_subkey_with_flags(reg_path, KEY_SET_VALUE)
@@ -541,6 +817,39 @@ fn set_auto_start(enabled: bool) -> AppResult<()> {
     Ok(())
 }
 
+/// 写入或删除XDG自启动项
+#[cfg(not(windows))]
+fn set_auto_start(enabled: bool) -> AppResult<()> {
+    use crate::core::config::{auto_start_command, auto_start_entry_path};
+    use std::fs;
+
+    let entry_path = auto_start_entry_path().ok_or_else(|| {
+        AppError::SystemError("无法确定自启动目录，请设置HOME或XDG_CONFIG_HOME".to_string())
+    })?;
+
+    if enabled {
+        let command = auto_start_command()
+            .ok_or_else(|| AppError::SystemError("获取可执行文件路径失败".to_string()))?;
+
+        if let Some(parent) = entry_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::SystemError(format!("无法创建自启动目录: {}", e)))?;
+        }
+
+        let content = format!(
+            "[Desktop Entry]\nType=Application\nName=AutoLoginGUET\nExec={}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+            command
+        );
+
+        fs::write(&entry_path, content)
+            .map_err(|e| AppError::SystemError(format!("无法写入自启动项: {}", e)))?;
+    } else {
+        // 忽略删除失败的情况（可能文件不存在）
+        let _ = fs::remove_file(&entry_path);
+    }
+    Ok(())
+}
+
 /// 验证账号格式
 /// 账号应该只包含数字，学生学号通常为10位，教师工号可能较短（3-12位）
 pub fn validate_username(username: &str) -> bool {
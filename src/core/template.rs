@@ -0,0 +1,554 @@
+//! 消息模板模块
+//!
+//! 解析`[message]`中的模板，支持：
+//! - 命名占位符，如`{campus}`、`{wan}`、`{flow}`，见[`Placeholder`]
+//! - 数字格式：`{elapsed:.1}`输出保留1位小数的秒数，`{flow:GB.1}`输出以GB为单位的剩余流量，
+//!   `{time:%H:%M}`按chrono格式输出时间
+//! - 可选片段：`{?flow}…{/flow}`，占位符有值时才输出中间的内容，可以嵌套
+//! - 旧的`%1`到`%7`，依次对应`campus`、`wan`、`elapsed`、`flow`、`forecast`、`balance`、`package`
+//!
+//! 无法识别的`{…}`按原样输出，方便发现拼写错误
+
+use crate::core::flow::format_flow_size;
+use chrono::NaiveDateTime;
+use chrono::format::{Item, StrftimeItems};
+use std::fmt::Write;
+
+/// `{time}`未指定格式时使用的格式
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+/// 数字格式未指定小数位数时保留的位数
+const DEFAULT_PRECISION: usize = 2;
+
+/// 模板占位符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    /// 校园网状态，`%1`
+    Campus,
+    /// 广域网状态，`%2`
+    Wan,
+    /// 操作耗时，`%3`
+    Elapsed,
+    /// 剩余流量，`%4`
+    Flow,
+    /// 流量消耗预测，`%5`
+    Forecast,
+    /// 账户余额，`%6`
+    Balance,
+    /// 套餐，`%7`
+    Package,
+    /// 账号
+    Username,
+    /// 运营商
+    Isp,
+    /// 本机在校园网中的IP地址
+    Ip,
+    /// 当前时间
+    Time,
+}
+
+impl Placeholder {
+    /// 按`%1`到`%7`的顺序排列的占位符
+    const LEGACY: [Placeholder; 7] = [
+        Placeholder::Campus,
+        Placeholder::Wan,
+        Placeholder::Elapsed,
+        Placeholder::Flow,
+        Placeholder::Forecast,
+        Placeholder::Balance,
+        Placeholder::Package,
+    ];
+
+    /// 从名称解析占位符
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "campus" => Some(Placeholder::Campus),
+            "wan" => Some(Placeholder::Wan),
+            "elapsed" => Some(Placeholder::Elapsed),
+            "flow" => Some(Placeholder::Flow),
+            "forecast" => Some(Placeholder::Forecast),
+            "balance" => Some(Placeholder::Balance),
+            "package" => Some(Placeholder::Package),
+            "username" => Some(Placeholder::Username),
+            "isp" => Some(Placeholder::Isp),
+            "ip" => Some(Placeholder::Ip),
+            "time" => Some(Placeholder::Time),
+            _ => None,
+        }
+    }
+
+    /// 从旧的`%N`编号解析占位符
+    fn from_legacy_index(index: char) -> Option<Self> {
+        let index = index.to_digit(10)? as usize;
+        Self::LEGACY.get(index.checked_sub(1)?).copied()
+    }
+
+    /// 解析占位符的格式，不支持的格式返回`None`
+    fn parse_format(&self, spec: &str) -> Option<Format> {
+        match self {
+            Placeholder::Time => {
+                let valid = !spec.is_empty()
+                    && StrftimeItems::new(spec).all(|item| !matches!(item, Item::Error));
+                valid.then(|| Format::Time(spec.to_string()))
+            }
+            Placeholder::Elapsed | Placeholder::Flow | Placeholder::Balance => {
+                let (unit, precision) = match spec.split_once('.') {
+                    Some((unit, precision)) => (unit, Some(precision.parse().ok()?)),
+                    None => (spec, None),
+                };
+                let unit = match unit {
+                    "" => None,
+                    "MB" if *self == Placeholder::Flow => Some(FlowUnit::MB),
+                    "GB" if *self == Placeholder::Flow => Some(FlowUnit::GB),
+                    _ => return None,
+                };
+                if unit.is_none() && precision.is_none() {
+                    return None;
+                }
+                Some(Format::Number {
+                    unit,
+                    precision: precision.unwrap_or(DEFAULT_PRECISION),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 占位符需要的数据
+    fn needs(&self) -> TemplateNeeds {
+        match self {
+            Placeholder::Wan => TemplateNeeds {
+                wan: true,
+                ..Default::default()
+            },
+            Placeholder::Flow | Placeholder::Balance | Placeholder::Package => TemplateNeeds {
+                account_info: true,
+                ..Default::default()
+            },
+            Placeholder::Forecast => TemplateNeeds {
+                account_info: true,
+                flow_forecast: true,
+                ..Default::default()
+            },
+            Placeholder::Ip => TemplateNeeds {
+                ip: true,
+                ..Default::default()
+            },
+            _ => TemplateNeeds::default(),
+        }
+    }
+}
+
+/// 剩余流量的单位
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlowUnit {
+    MB,
+    GB,
+}
+
+/// 占位符的格式
+#[derive(Debug, Clone, PartialEq)]
+enum Format {
+    /// 只输出数字，剩余流量未指定单位时自动选择MB或GB并附带单位
+    Number {
+        unit: Option<FlowUnit>,
+        precision: usize,
+    },
+    /// chrono时间格式
+    Time(String),
+}
+
+/// 模板需要的数据，服务层据此决定是否检查广域网、查询自助服务系统等
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TemplateNeeds {
+    /// 广域网状态
+    pub wan: bool,
+    /// 自助服务系统的账户信息（剩余流量、余额和套餐）
+    pub account_info: bool,
+    /// 流量消耗预测
+    pub flow_forecast: bool,
+    /// 本机IP地址
+    pub ip: bool,
+}
+
+impl TemplateNeeds {
+    /// 合并两个模板需要的数据
+    pub fn merge(self, other: TemplateNeeds) -> TemplateNeeds {
+        TemplateNeeds {
+            wan: self.wan || other.wan,
+            account_info: self.account_info || other.account_info,
+            flow_forecast: self.flow_forecast || other.flow_forecast,
+            ip: self.ip || other.ip,
+        }
+    }
+}
+
+/// 渲染模板使用的数据，没有的数据保持为空
+#[derive(Debug, Clone, Default)]
+pub struct TemplateValues {
+    /// 校园网状态消息
+    pub campus: String,
+    /// 广域网状态消息
+    pub wan: String,
+    /// 耗时（秒）
+    pub elapsed_secs: Option<f64>,
+    /// 剩余流量（MB）
+    pub left_flow_mb: Option<f64>,
+    /// 流量消耗预测消息
+    pub forecast: String,
+    /// 账户余额（元）
+    pub balance: Option<f64>,
+    /// 套餐消息
+    pub package: String,
+    /// 账号
+    pub username: String,
+    /// 运营商名称
+    pub isp: String,
+    /// 本机IP地址
+    pub ip: String,
+    /// 当前时间
+    pub time: Option<NaiveDateTime>,
+}
+
+impl TemplateValues {
+    /// 占位符的默认文本
+    fn text(&self, placeholder: Placeholder) -> String {
+        match placeholder {
+            Placeholder::Campus => self.campus.clone(),
+            Placeholder::Wan => self.wan.clone(),
+            Placeholder::Elapsed => self
+                .elapsed_secs
+                .map(|secs| format!("用时{:.2}秒", secs))
+                .unwrap_or_default(),
+            Placeholder::Flow => self
+                .left_flow_mb
+                .map(|flow_mb| {
+                    if flow_mb == 0.0 {
+                        "流量耗尽，限速不限量生效".to_string()
+                    } else {
+                        format!("剩余流量{}", format_flow_size(flow_mb))
+                    }
+                })
+                .unwrap_or_default(),
+            Placeholder::Forecast => self.forecast.clone(),
+            Placeholder::Balance => self
+                .balance
+                .map(|balance| format!("余额{:.2}元", balance))
+                .unwrap_or_default(),
+            Placeholder::Package => self.package.clone(),
+            Placeholder::Username => self.username.clone(),
+            Placeholder::Isp => self.isp.clone(),
+            Placeholder::Ip => self.ip.clone(),
+            Placeholder::Time => self.format_time(DEFAULT_TIME_FORMAT),
+        }
+    }
+
+    /// 按指定格式输出占位符
+    fn formatted(&self, placeholder: Placeholder, format: &Format) -> String {
+        match format {
+            Format::Time(spec) => self.format_time(spec),
+            Format::Number { unit, precision } => {
+                let value = match placeholder {
+                    Placeholder::Elapsed => self.elapsed_secs,
+                    Placeholder::Flow => self.left_flow_mb,
+                    Placeholder::Balance => self.balance,
+                    _ => None,
+                };
+                let Some(value) = value else {
+                    return String::new();
+                };
+
+                match unit {
+                    Some(FlowUnit::MB) => format!("{:.*}", precision, value),
+                    Some(FlowUnit::GB) => format!("{:.*}", precision, value / 1024.0),
+                    None if placeholder == Placeholder::Flow && value >= 1024.0 => {
+                        format!("{:.*}GB", precision, value / 1024.0)
+                    }
+                    None if placeholder == Placeholder::Flow => {
+                        format!("{:.*}MB", precision, value)
+                    }
+                    None => format!("{:.*}", precision, value),
+                }
+            }
+        }
+    }
+
+    /// 按chrono格式输出时间，格式化失败时为空
+    fn format_time(&self, spec: &str) -> String {
+        let mut result = String::new();
+        if let Some(time) = self.time
+            && write!(result, "{}", time.format(spec)).is_err()
+        {
+            result.clear();
+        }
+        result
+    }
+}
+
+/// 模板片段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// 原样输出的文本
+    Text(String),
+    /// 占位符
+    Value {
+        placeholder: Placeholder,
+        format: Option<Format>,
+    },
+    /// 可选片段，占位符有值时才输出
+    Section {
+        placeholder: Placeholder,
+        body: Vec<Segment>,
+    },
+}
+
+/// `{…}`中的内容
+enum Tag {
+    Value(Placeholder, Option<Format>),
+    Open(Placeholder),
+    Close(Placeholder),
+}
+
+impl Tag {
+    /// 解析`{…}`中的内容，无法识别时返回`None`
+    fn parse(tag: &str) -> Option<Self> {
+        if let Some(name) = tag.strip_prefix('?') {
+            return Placeholder::from_name(name).map(Tag::Open);
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            return Placeholder::from_name(name).map(Tag::Close);
+        }
+
+        match tag.split_once(':') {
+            Some((name, spec)) => {
+                let placeholder = Placeholder::from_name(name)?;
+                let format = placeholder.parse_format(spec)?;
+                Some(Tag::Value(placeholder, Some(format)))
+            }
+            None => Placeholder::from_name(tag).map(|placeholder| Tag::Value(placeholder, None)),
+        }
+    }
+}
+
+/// 解析后的消息模板
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageTemplate {
+    segments: Vec<Segment>,
+}
+
+impl MessageTemplate {
+    /// 解析模板，无法识别的内容按原样保留
+    pub fn parse(template: &str) -> Self {
+        let mut pos = 0;
+        let (segments, _) = parse_segments(template, &mut pos, None);
+        Self { segments }
+    }
+
+    /// 模板需要的数据
+    pub fn needs(&self) -> TemplateNeeds {
+        segments_needs(&self.segments)
+    }
+
+    /// 是否包含占位符，原样输出的内容不算
+    pub fn has_placeholder(&self) -> bool {
+        segments_have_placeholder(&self.segments)
+    }
+
+    /// 使用数据渲染模板
+    pub fn render(&self, values: &TemplateValues) -> String {
+        let mut result = String::new();
+        render_segments(&self.segments, values, &mut result);
+        result
+    }
+}
+
+/// 解析到`open`对应的结束标记或模板末尾，返回解析出的片段和是否遇到了结束标记
+fn parse_segments(
+    template: &str,
+    pos: &mut usize,
+    open: Option<Placeholder>,
+) -> (Vec<Segment>, bool) {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+
+    fn flush(text: &mut String, segments: &mut Vec<Segment>) {
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(text)));
+        }
+    }
+
+    while let Some(rest) = template.get(*pos..).filter(|rest| !rest.is_empty()) {
+        let mut chars = rest.chars();
+        let ch = chars.next().unwrap_or_default();
+
+        if ch == '%'
+            && let Some(placeholder) = chars.next().and_then(Placeholder::from_legacy_index)
+        {
+            flush(&mut text, &mut segments);
+            segments.push(Segment::Value {
+                placeholder,
+                format: None,
+            });
+            *pos += 2;
+            continue;
+        }
+
+        if ch == '{'
+            && let Some(end) = rest.find('}')
+            && let Some(tag) = Tag::parse(&rest[1..end])
+        {
+            match tag {
+                Tag::Value(placeholder, format) => {
+                    flush(&mut text, &mut segments);
+                    segments.push(Segment::Value {
+                        placeholder,
+                        format,
+                    });
+                    *pos += end + 1;
+                    continue;
+                }
+                Tag::Open(placeholder) => {
+                    flush(&mut text, &mut segments);
+                    *pos += end + 1;
+                    let (body, closed) = parse_segments(template, pos, Some(placeholder));
+                    if closed {
+                        segments.push(Segment::Section { placeholder, body });
+                    } else {
+                        // 没有结束标记时按原样输出开始标记
+                        segments.push(Segment::Text(rest[..=end].to_string()));
+                        segments.extend(body);
+                    }
+                    continue;
+                }
+                Tag::Close(placeholder) if Some(placeholder) == open => {
+                    flush(&mut text, &mut segments);
+                    *pos += end + 1;
+                    return (segments, true);
+                }
+                Tag::Close(_) => {}
+            }
+        }
+
+        text.push(ch);
+        *pos += ch.len_utf8();
+    }
+
+    flush(&mut text, &mut segments);
+    (segments, false)
+}
+
+/// 片段需要的数据
+fn segments_needs(segments: &[Segment]) -> TemplateNeeds {
+    segments
+        .iter()
+        .fold(TemplateNeeds::default(), |needs, segment| match segment {
+            Segment::Text(_) => needs,
+            Segment::Value { placeholder, .. } => needs.merge(placeholder.needs()),
+            Segment::Section { placeholder, body } => {
+                needs.merge(placeholder.needs()).merge(segments_needs(body))
+            }
+        })
+}
+
+/// 片段中是否包含占位符
+fn segments_have_placeholder(segments: &[Segment]) -> bool {
+    segments.iter().any(|segment| match segment {
+        Segment::Text(_) => false,
+        Segment::Value { .. } => true,
+        Segment::Section { body, .. } => segments_have_placeholder(body),
+    })
+}
+
+/// 渲染片段
+fn render_segments(segments: &[Segment], values: &TemplateValues, result: &mut String) {
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => result.push_str(text),
+            Segment::Value {
+                placeholder,
+                format: None,
+            } => result.push_str(&values.text(*placeholder)),
+            Segment::Value {
+                placeholder,
+                format: Some(format),
+            } => result.push_str(&values.formatted(*placeholder, format)),
+            Segment::Section { placeholder, body } => {
+                if !values.text(*placeholder).is_empty() {
+                    render_segments(body, values, result);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_named_legacy_and_sections() {
+        let values = TemplateValues {
+            campus: "已登录校园网".to_string(),
+            elapsed_secs: Some(1.234),
+            left_flow_mb: Some(12636.16),
+            username: "2100000000".to_string(),
+            time: NaiveDateTime::parse_from_str("2025-10-01 08:05:09", "%Y-%m-%d %H:%M:%S").ok(),
+            ..Default::default()
+        };
+        let render = |template: &str| MessageTemplate::parse(template).render(&values);
+
+        assert_eq!(
+            render("%1 %3 %4"),
+            "已登录校园网 用时1.23秒 剩余流量12.34GB"
+        );
+        assert_eq!(
+            render("{username} {campus}{?wan}，{wan}{/wan}{?flow}（{flow:GB.1}GB）{/flow}"),
+            "2100000000 已登录校园网（12.3GB）"
+        );
+        assert_eq!(
+            render("{elapsed:.0}s {flow:.1} {flow:MB.0} {time} {time:%m-%d}"),
+            "1s 12.3GB 12636 08:05:09 10-01"
+        );
+        assert_eq!(render("{balance:.1}|{?balance}x{/balance}"), "|");
+
+        // 无法识别的内容按原样输出
+        assert_eq!(
+            render("{name} {campus:.1} {?flow}未结束 {/wan} {{campus}} %8 {time:%Q}"),
+            "{name} {campus:.1} {?flow}未结束 {/wan} {已登录校园网} %8 {time:%Q}"
+        );
+    }
+
+    #[test]
+    fn test_template_needs() {
+        let needs = |template: &str| MessageTemplate::parse(template).needs();
+
+        assert_eq!(
+            needs("{campus} {elapsed} {username} {time}"),
+            TemplateNeeds::default()
+        );
+        assert_eq!(
+            needs("%1 %2\n%3 %4"),
+            TemplateNeeds {
+                wan: true,
+                account_info: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            needs("{?ip}{ip}{/ip} {?campus}%5{/campus}"),
+            TemplateNeeds {
+                account_info: true,
+                flow_forecast: true,
+                ip: true,
+                ..Default::default()
+            }
+        );
+        // 原样输出的内容不需要数据
+        assert_eq!(needs("{wan:.1} {?flow}"), TemplateNeeds::default());
+
+        let has_placeholder = |template: &str| MessageTemplate::parse(template).has_placeholder();
+        assert!(has_placeholder("{time}"));
+        assert!(has_placeholder("{?flow}（{flow}）{/flow}"));
+        assert!(!has_placeholder("{name} %8 {?flow}{/flow}"));
+    }
+}
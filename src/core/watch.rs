@@ -0,0 +1,279 @@
+//! 守护模式模块
+//!
+//! 长期运行并定时检测网络状态，认证会话失效（如夜间超时、DHCP续租）后自动重新登录
+
+use crate::core::config::{ConfigData, WatchConfig};
+use crate::core::events::notify_network_status_checked;
+use crate::core::flow_switch::FlowSwitchDecision;
+use crate::core::message::{CampusNetworkStatus, WanStatus};
+use crate::core::service::AuthService;
+use std::time::{Duration, Instant};
+
+/// 退避倍数的最大指数，避免间隔溢出
+const MAX_BACKOFF_EXPONENT: u32 = 10;
+
+/// 守护模式观测到的连接状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    /// 已登录校园网，附带广域网状态
+    Online(WanStatus),
+    /// 未登录校园网
+    LoggedOut,
+    /// 无法访问校园网认证页面（如未接入校园网）
+    PortalUnreachable,
+}
+
+impl ConnectionState {
+    /// 转换为消息文本
+    pub fn to_message(&self) -> String {
+        match self {
+            ConnectionState::Online(WanStatus::CheckFailed) => {
+                CampusNetworkStatus::AlreadyLoggedIn.to_message()
+            }
+            ConnectionState::Online(wan_status) => format!(
+                "{} {}",
+                CampusNetworkStatus::AlreadyLoggedIn.to_message(),
+                wan_status.to_message()
+            ),
+            ConnectionState::LoggedOut => CampusNetworkStatus::NotLoggedIn.to_message(),
+            ConnectionState::PortalUnreachable => "无法访问校园网认证页面".to_string(),
+        }
+    }
+
+    /// 转换为事件使用的校园网和广域网状态
+    fn to_statuses(&self) -> (CampusNetworkStatus, WanStatus) {
+        match self {
+            ConnectionState::Online(wan_status) => {
+                (CampusNetworkStatus::AlreadyLoggedIn, wan_status.clone())
+            }
+            ConnectionState::LoggedOut | ConnectionState::PortalUnreachable => {
+                (CampusNetworkStatus::NotLoggedIn, WanStatus::CheckFailed)
+            }
+        }
+    }
+}
+
+/// 网络守护器
+///
+/// 按`[watch]`配置的间隔检测网络状态，未登录时依次使用默认账号和备用档案自动登录；
+/// 连续登录失败时按指数退避延长检测间隔，所有状态变化都会记录到日志
+pub struct NetworkWatcher {
+    auth_service: AuthService,
+    config: ConfigData,
+    last_state: Option<ConnectionState>,
+    consecutive_failures: u32,
+    last_flow_check: Option<Instant>,
+}
+
+impl NetworkWatcher {
+    /// 创建新的网络守护器
+    pub fn new(auth_service: AuthService, config: ConfigData) -> Self {
+        Self {
+            auth_service,
+            config,
+            last_state: None,
+            consecutive_failures: 0,
+            last_flow_check: None,
+        }
+    }
+
+    /// 持续运行守护循环
+    pub async fn run(&mut self) {
+        let _ = self.auth_service.get_message_center().log_event(
+            "INFO",
+            &format!(
+                "守护模式已启动，检测间隔{}秒",
+                self.config.watch.interval_secs
+            ),
+        );
+
+        loop {
+            let delay = self.tick().await;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// 执行一次检测，返回距离下次检测的等待时间
+    pub async fn tick(&mut self) -> Duration {
+        let state = self.observe().await;
+        self.record_transition(&state);
+
+        match state {
+            ConnectionState::LoggedOut => {
+                if self.try_login().await {
+                    self.consecutive_failures = 0;
+                } else {
+                    self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                    let delay = self.next_delay();
+                    let _ = self.auth_service.get_message_center().log_event(
+                        "WARNING",
+                        &format!(
+                            "自动登录失败（连续{}次），{}秒后重试",
+                            self.consecutive_failures,
+                            delay.as_secs()
+                        ),
+                    );
+                    return delay;
+                }
+            }
+            ConnectionState::Online(_) => {
+                self.consecutive_failures = 0;
+                self.check_flow_switch().await;
+            }
+            ConnectionState::PortalUnreachable => {}
+        }
+
+        self.next_delay()
+    }
+
+    /// 检测当前连接状态
+    async fn observe(&self) -> ConnectionState {
+        match self.auth_service.check_campus_network().await {
+            Ok(CampusNetworkStatus::NotLoggedIn) => ConnectionState::LoggedOut,
+            Ok(_) => {
+                let wan_status = if self.config.watch.check_wan {
+                    self.auth_service.check_wan_network().await
+                } else {
+                    WanStatus::CheckFailed
+                };
+                ConnectionState::Online(wan_status)
+            }
+            Err(_) => ConnectionState::PortalUnreachable,
+        }
+    }
+
+    /// 记录状态变化
+    fn record_transition(&mut self, state: &ConnectionState) {
+        if self.last_state.as_ref() == Some(state) {
+            return;
+        }
+
+        let message = match &self.last_state {
+            Some(last_state) => format!(
+                "网络状态变化: {} -> {}",
+                last_state.to_message(),
+                state.to_message()
+            ),
+            None => format!("当前网络状态: {}", state.to_message()),
+        };
+
+        let level = match state {
+            ConnectionState::Online(_) => "INFO",
+            _ => "WARNING",
+        };
+        let _ = self
+            .auth_service
+            .get_message_center()
+            .log_event(level, &message);
+
+        let (campus_status, wan_status) = state.to_statuses();
+        notify_network_status_checked(
+            self.auth_service.get_event_bus(),
+            campus_status,
+            wan_status,
+            &message,
+        );
+
+        self.last_state = Some(state.clone());
+    }
+
+    /// 依次使用默认账号和备用档案尝试登录，返回是否成功
+    async fn try_login(&self) -> bool {
+        match self.auth_service.login_with_profiles(&self.config).await {
+            Ok(result) => result.success,
+            Err(e) => {
+                let _ = self
+                    .auth_service
+                    .get_message_center()
+                    .log_event("ERROR", &format!("自动登录失败: {}", e));
+                false
+            }
+        }
+    }
+
+    /// 按`[flow_switch]`配置的间隔检查剩余流量，必要时切换账号
+    async fn check_flow_switch(&mut self) {
+        let flow_switch = &self.config.flow_switch;
+        if !flow_switch.enabled {
+            return;
+        }
+
+        let interval = Duration::from_secs(flow_switch.check_interval_secs);
+        if self
+            .last_flow_check
+            .is_some_and(|last_check| last_check.elapsed() < interval)
+        {
+            return;
+        }
+        self.last_flow_check = Some(Instant::now());
+
+        match self.auth_service.apply_flow_switch(&mut self.config).await {
+            Ok(FlowSwitchDecision::Stay) => {}
+            Ok(_) => {
+                // 切换后重新检测，记录新账号的登录状态
+                self.last_state = None;
+            }
+            Err(e) => {
+                let _ = self
+                    .auth_service
+                    .get_message_center()
+                    .log_event("WARNING", &format!("流量切换检查失败: {}", e));
+            }
+        }
+    }
+
+    /// 计算下次检测的等待时间
+    fn next_delay(&self) -> Duration {
+        next_delay(&self.config.watch, self.consecutive_failures)
+    }
+}
+
+/// 按连续失败次数计算下次检测的等待时间
+fn next_delay(watch: &WatchConfig, consecutive_failures: u32) -> Duration {
+    let interval = watch.interval_secs.max(1);
+
+    if consecutive_failures == 0 {
+        return Duration::from_secs(interval);
+    }
+
+    let exponent = consecutive_failures.min(MAX_BACKOFF_EXPONENT);
+    let backoff = interval.saturating_mul(1 << exponent);
+    let max_backoff = watch.max_backoff_secs.max(interval);
+
+    Duration::from_secs(backoff.min(max_backoff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_delay_backs_off_exponentially() {
+        let watch = WatchConfig {
+            interval_secs: 60,
+            max_backoff_secs: 1800,
+            check_wan: false,
+        };
+        assert_eq!(next_delay(&watch, 0), Duration::from_secs(60));
+        assert_eq!(next_delay(&watch, 1), Duration::from_secs(120));
+        assert_eq!(next_delay(&watch, 4), Duration::from_secs(960));
+        assert_eq!(next_delay(&watch, 5), Duration::from_secs(1800));
+        assert_eq!(next_delay(&watch, u32::MAX), Duration::from_secs(1800));
+
+        // 间隔为0时按1秒计算，最大退避间隔不小于检测间隔
+        let watch = WatchConfig {
+            interval_secs: 0,
+            max_backoff_secs: 0,
+            check_wan: false,
+        };
+        assert_eq!(next_delay(&watch, 0), Duration::from_secs(1));
+        assert_eq!(next_delay(&watch, 3), Duration::from_secs(1));
+
+        let watch = WatchConfig {
+            interval_secs: u64::MAX,
+            max_backoff_secs: u64::MAX,
+            check_wan: false,
+        };
+        assert_eq!(next_delay(&watch, 3), Duration::from_secs(u64::MAX));
+    }
+}
@@ -12,6 +12,8 @@ use crate::gui::init::{init_app_config, init_app_logs_and_network};
 use crate::gui::state::GuiConfigWithData;
 use autologinguet_core::core::dto::GuiConfigDto;
 use autologinguet_core::core::events::GuiEventHandlerMessage;
+use autologinguet_core::core::flow::OnlineDevice;
+use autologinguet_core::core::secret_string::SecretString;
 use autologinguet_core::core::service::{AuthService, validate_password, validate_username};
 
 /// GUI主应用组件
@@ -26,6 +28,7 @@ fn app() -> Element {
     let receiver: Signal<Option<Receiver<GuiEventHandlerMessage>>> = use_signal(|| None);
     let mut username_invalid = use_signal(|| false);
     let mut password_invalid = use_signal(|| false);
+    let online_devices = use_signal(Vec::<OnlineDevice>::new);
 
     // 居中窗口
     use_effect(move || {
@@ -49,7 +52,6 @@ fn app() -> Element {
     });
 
     // 检查并同步注册表状态与配置文件状态
-    #[cfg(windows)]
     use_effect(move || {
         spawn(async move {
             loop {
@@ -111,13 +113,13 @@ fn app() -> Element {
     };
 
     let on_password_input = move |e: Event<FormData>| {
-        gui_config.write().password = e.value();
+        gui_config.write().password = SecretString::new(e.value());
         password_invalid.set(false);
     };
 
     let on_password_blur = move |_| {
         let current_value = gui_config.read().password.clone();
-        if !validate_password(&current_value) && !current_value.is_empty() {
+        if !validate_password(current_value.expose()) && !current_value.is_empty() {
             *message.write() = "密码必须包含大小写字母、数字和符号，长度8-32位".to_string();
             password_invalid.set(true);
         } else {
@@ -129,6 +131,23 @@ fn app() -> Element {
         gui_config.write().isp = e.value();
     };
 
+    let on_profile_select = move |e: Event<FormData>| {
+        let name = e.value();
+        if let Some(ref service) = *auth_service.read() {
+            match crate::gui::gui_service::switch_profile(
+                service,
+                &name,
+                gui_config,
+                gui_config_with_data,
+            ) {
+                Ok(_) => *message.write() = format!("已切换到账号档案: {}", name),
+                Err(e) => *message.write() = format!("切换账号档案失败: {}", e),
+            }
+        } else {
+            *message.write() = "认证服务未初始化".to_string();
+        }
+    };
+
     let on_immediate_login = move |_| {
         if debug_info().enable_debug {
             *message.write() = "正在Debug登录...".to_string();
@@ -168,10 +187,21 @@ fn app() -> Element {
         }
     };
 
+    let on_list_devices = move |_| {
+        *message.write() = "正在查询在线设备...".to_string();
+        spawn(async move {
+            if let Some(ref service) = *auth_service.read() {
+                crate::gui::gui_service::load_online_devices(service, online_devices, message)
+                    .await;
+            } else {
+                *message.write() = "认证服务未初始化".to_string();
+            }
+        });
+    };
+
     let on_auto_start_toggle = move |e: Event<FormData>| {
         let new_value = e.value() == "true";
 
-        #[cfg(windows)]
         spawn(async move {
             if let Some(ref service) = *auth_service.read() {
                 match crate::gui::gui_service::set_auto_start(
@@ -231,6 +261,21 @@ fn app() -> Element {
                     }
                 }
 
+                // 配置了账号档案时显示档案切换
+                if !gui_config_with_data().full_config.profiles.accounts.is_empty() {
+                    div { class: "form-group",
+                        div { class: "select-row",
+                            select {
+                                value: "{gui_config_with_data().full_config.profiles.default}",
+                                onchange: on_profile_select,
+                                for profile in gui_config_with_data().full_config.profiles.accounts {
+                                    option { key: "{profile.name}", value: "{profile.name}", "{profile.name}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
                 div { class: "form-group",
                     div { class: "form-row",
                         input {
@@ -249,7 +294,7 @@ fn app() -> Element {
                         div { class: "password-container",
                             input {
                                 r#type: "password",
-                                value: "{gui_config.read().password}",
+                                value: "{gui_config.read().password.expose()}",
                                 class: if *password_invalid.read() { "invalid" } else { "" },
                                 oninput: on_password_input,
                                 onblur: on_password_blur,
@@ -257,7 +302,7 @@ fn app() -> Element {
                             }
                             div {
                                 class: "password-hint",
-                                match (gui_config.read().password.is_empty(), !gui_config_with_data.read().encrypted_password.is_empty()) {
+                                match (gui_config.read().password.is_empty(), gui_config_with_data.read().full_config.secret.has_password(&gui_config_with_data.read().encrypted_password)) {
                                     (true, true) => "当前已记录密码\n出于安全考虑不予显示",
                                     (true, false) => "输入密码",
                                     _ => ""
@@ -329,6 +374,41 @@ fn app() -> Element {
                         onclick: on_immediate_login,
                         "立即登录"
                     }
+                    button {
+                        class: "btn btn-info",
+                        onclick: on_list_devices,
+                        title: "查看当前账号的在线设备，可强制下线",
+                        "在线设备"
+                    }
+                }
+
+                // 查询过在线设备后显示设备列表
+                if !online_devices().is_empty() {
+                    div { class: "device-list",
+                        for device in online_devices() {
+                            div { class: "device-item", key: "{device.session_id}",
+                                span { title: "{device.mac} 登录于{device.login_time}", "{device.ip}" }
+                                button {
+                                    class: "btn btn-small",
+                                    onclick: move |_| {
+                                        let device = device.clone();
+                                        spawn(async move {
+                                            if let Some(ref service) = *auth_service.read() {
+                                                crate::gui::gui_service::kick_device(
+                                                    service,
+                                                    device,
+                                                    online_devices,
+                                                    message,
+                                                )
+                                                .await;
+                                            }
+                                        });
+                                    },
+                                    "下线"
+                                }
+                            }
+                        }
+                    }
                 }
 
                 if !message().is_empty() {
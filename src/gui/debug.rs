@@ -7,6 +7,8 @@ use autologinguet_core::core::dto::GuiConfigDto;
 use autologinguet_core::core::events::EventBus;
 use autologinguet_core::core::message::MessageCenter;
 use autologinguet_core::core::network::NetworkManager;
+use autologinguet_core::core::secret_store::secret_store_from_config;
+use autologinguet_core::core::secret_string::SecretString;
 use dioxus::prelude::*;
 
 const DEFAULT_LOGIN_IP: &str = "http://10.0.1.5/";
@@ -22,9 +24,12 @@ pub struct DebugInfo {
     pub error_message: String,
 }
 
-/// 将`GuiConfigDto`转换为`ConfigData`用于Debug登录
+/// 将`GuiConfigDto`转换为`ConfigData`用于Debug登录，不保存输入的密码
 fn gui_config_to_config(gui_config: &GuiConfigDto) -> ConfigData {
-    let config: ConfigData = gui_config.clone().into();
+    let mut config = ConfigData::load_existing_or_default();
+    config.account.username = gui_config.username.clone();
+    config.account.encrypted_password = gui_config.encrypted_password.clone();
+    config.account.isp = normalize_isp(&gui_config.isp);
     config
 }
 
@@ -44,15 +49,18 @@ pub fn perform_debug_network_request(
 
         message_center.log_event("INFO", "开始调试网络请求...").ok();
 
-        let password = autologinguet_core::core::crypto::decrypt_config_password(
-            &config_to_login.account.encrypted_password,
-        )
-        .unwrap_or_else(|e| {
-            message_center
-                .log_event("ERROR", &format!("解密密码失败: {}", e))
-                .ok();
-            String::new()
-        });
+        let password = if !gui_config.password.is_empty() {
+            gui_config.password.clone()
+        } else {
+            secret_store_from_config(&config_to_login.secret)
+                .get_password(&config_to_login.account)
+                .unwrap_or_else(|e| {
+                    message_center
+                        .log_event("ERROR", &format!("解密密码失败: {}", e))
+                        .ok();
+                    SecretString::default()
+                })
+        };
 
         let actual_isp = normalize_isp(&config_to_login.account.isp);
 
@@ -107,6 +107,15 @@ impl EventHandler for GuiEventHandler {
                     message: message.to_string(),
                 });
             }
+            AppEvent::ConfigLoaded {
+                success: _,
+                message,
+            } => {
+                let _ = self.sender.send(GuiEventHandlerMessage::LogRecorded {
+                    level: "INFO".to_string(),
+                    message: message.to_string(),
+                });
+            }
             AppEvent::NotificationShown { title: _, message } => {
                 let _ = self.sender.send(GuiEventHandlerMessage::LogRecorded {
                     level: "INFO".to_string(),
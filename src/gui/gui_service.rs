@@ -4,12 +4,12 @@
 
 use crate::gui::gui_event::GuiEventHandler;
 use crate::gui::state::GuiConfigWithData;
-use autologinguet_core::core::config::{ConfigData, load_config, normalize_isp};
-use autologinguet_core::core::crypto::{
-    decrypt_password_with_machine_key, generate_encrypted_password,
+use autologinguet_core::core::config::{
+    ConfigData, load_config, load_config_with_report, normalize_isp,
 };
 use autologinguet_core::core::error::AppResult;
-use autologinguet_core::core::events::GuiEventHandlerMessage;
+use autologinguet_core::core::events::{GuiEventHandlerMessage, notify_config_loaded};
+use autologinguet_core::core::flow::OnlineDevice;
 use autologinguet_core::core::service::{validate_password, validate_username};
 use autologinguet_core::{AuthService, GuiConfigDto};
 use dioxus::prelude::*;
@@ -44,8 +44,8 @@ pub async fn init_config(
     mut gui_config: Signal<GuiConfigDto>,
     mut gui_config_with_data: Signal<GuiConfigWithData>,
 ) -> Option<(AuthService, Receiver<GuiEventHandlerMessage>)> {
-    match load_config() {
-        Ok(config) => {
+    match load_config_with_report() {
+        Ok((config, report)) => {
             let gui_config_data = GuiConfigWithData::from(config.clone());
             *gui_config_with_data.write() = gui_config_data.clone();
             *gui_config.write() = gui_config_data.gui_config;
@@ -54,6 +54,10 @@ pub async fn init_config(
             let (event_handler, receiver) = GuiEventHandler::new();
             auth_service.set_event_handler(Box::new(event_handler));
 
+            if report.has_changes() {
+                notify_config_loaded(auth_service.get_event_bus(), true, &report.to_message());
+            }
+
             Some((auth_service, receiver))
         }
         Err(_) => None,
@@ -98,10 +102,58 @@ pub async fn save_account_config(
     auth_service: &AuthService,
     gui_config: &GuiConfigDto,
 ) -> AppResult<()> {
-    let config_to_save: ConfigData = gui_config.clone().into();
+    let config_to_save = gui_config.to_config()?;
     auth_service.save_config(&config_to_save)
 }
 
+/// 切换默认账号档案，并用档案中的账号刷新界面
+pub fn switch_profile(
+    auth_service: &AuthService,
+    name: &str,
+    mut gui_config: Signal<GuiConfigDto>,
+    mut gui_config_with_data: Signal<GuiConfigWithData>,
+) -> AppResult<()> {
+    let mut config = load_config()?;
+    config.switch_profile(name)?;
+    auth_service.save_config(&config)?;
+
+    let gui_config_data = GuiConfigWithData::from(config);
+    *gui_config.write() = gui_config_data.gui_config.clone();
+    *gui_config_with_data.write() = gui_config_data;
+    Ok(())
+}
+
+/// 查询在线设备并刷新列表
+pub async fn load_online_devices(
+    auth_service: &AuthService,
+    mut devices: Signal<Vec<OnlineDevice>>,
+    mut message: Signal<String>,
+) {
+    match auth_service.list_online_devices().await {
+        Ok(list) => {
+            *message.write() = format!("当前在线设备{}台", list.len());
+            *devices.write() = list;
+        }
+        Err(e) => *message.write() = format!("获取在线设备失败: {}", e),
+    }
+}
+
+/// 强制设备下线并刷新列表
+pub async fn kick_device(
+    auth_service: &AuthService,
+    device: OnlineDevice,
+    devices: Signal<Vec<OnlineDevice>>,
+    mut message: Signal<String>,
+) {
+    match auth_service.kick_device(&device.session_id).await {
+        Ok(_) => {
+            load_online_devices(auth_service, devices, message).await;
+            *message.write() = format!("已强制下线: {}", device.ip);
+        }
+        Err(e) => *message.write() = format!("强制下线失败: {}", e),
+    }
+}
+
 /// 执行登录操作
 pub async fn perform_login(
     auth_service: &AuthService,
@@ -119,7 +171,7 @@ pub async fn perform_login(
     }
 
     // 使用通用验证函数验证密码（如果提供了新密码）
-    if !gui_config.password.is_empty() && !validate_password(&gui_config.password) {
+    if !gui_config.password.is_empty() && !validate_password(gui_config.password.expose()) {
         *message.write() = "密码长度不正确，请输入8-32位密码".to_string();
         return Err(autologinguet_core::core::error::AppError::ConfigError(
             "密码长度不正确".to_string(),
@@ -169,7 +221,7 @@ pub async fn perform_login(
         gui_config.password.clone()
     } else {
         autologinguet_core::core::crypto::handle_password_decryption_error_with_default(
-            decrypt_password_with_machine_key(&config_to_login.account.encrypted_password),
+            auth_service.get_password(&config_to_login.account),
             auth_service.get_event_bus(),
         )
     };
@@ -213,11 +265,8 @@ fn get_login_config(
 
     config_to_login.account.username = gui_config.username.clone();
 
-    // 只要密码不为空就使用新密码，否则使用已保存的加密密码
-    if !gui_config.password.is_empty() {
-        config_to_login.account.encrypted_password =
-            generate_encrypted_password(&gui_config.password);
-    } else if !gui_config_with_data.encrypted_password.is_empty() {
+    // 输入了新密码时直接使用明文登录，否则使用已保存的密码
+    if gui_config.password.is_empty() && !gui_config_with_data.encrypted_password.is_empty() {
         config_to_login.account.encrypted_password =
             gui_config_with_data.encrypted_password.clone();
     }
@@ -228,14 +277,13 @@ fn get_login_config(
 }
 
 /// 设置开机自启
-#[cfg(windows)]
 pub async fn set_auto_start(
     auth_service: &AuthService,
     enabled: bool,
     gui_config: &GuiConfigDto,
     gui_config_with_data: &GuiConfigWithData,
 ) -> AppResult<()> {
-    let mut config_to_save: ConfigData = gui_config.clone().into();
+    let mut config_to_save = gui_config.to_config()?;
     config_to_save.settings.auto_start = enabled;
 
     if !gui_config.username.is_empty() && !gui_config.password.is_empty() {
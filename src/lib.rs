@@ -1,5 +1,6 @@
 //! AutoLoginGUET 核心库
 
+pub mod cli;
 pub mod core;
 
 pub use core::config::normalize_isp;
@@ -7,11 +8,8 @@ pub use core::dto::GuiConfigDto;
 pub use core::error::AppError;
 pub use core::events::{AppEvent, EventHandler};
 pub use core::message::MessageCenter;
-pub use core::service::{AuthService, LoginResult};
+pub use core::service::{AuthService, LoginResult, LogoutResult};
 
 // GUI相关导出
-#[cfg(feature = "gui")]
-pub mod gui;
-
 #[cfg(feature = "gui")]
 pub use core::events::GuiEventHandlerMessage;
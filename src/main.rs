@@ -4,8 +4,9 @@
 use crate::gui::app::launch_gui;
 use autologinguet_core::AppError;
 use autologinguet_core::AuthService;
-use autologinguet_core::core::config::load_config;
+use autologinguet_core::core::config::load_config_with_report;
 use autologinguet_core::core::error::AppResult;
+use autologinguet_core::core::paths::{config_path_from_args, set_config_path};
 use std::env;
 use std::process;
 
@@ -21,15 +22,15 @@ fn main() -> AppResult<()> {
         }
     }
 
-    if let Ok(exe_path) = env::current_exe()
-        && let Some(exe_dir) = exe_path.parent() {
-            let _ = env::set_current_dir(exe_dir);
-        }
-
     let args: Vec<String> = env::args().collect();
 
+    // 通过"--config <path>"指定配置文件
+    if let Some(path) = config_path_from_args(&args) {
+        set_config_path(path);
+    }
+
     // 只有在通过命令行传递"-silent"参数时才进入静默模式
-    let is_silent_mode = args.len() > 1 && args[1] == "-silent";
+    let is_silent_mode = args.iter().skip(1).any(|arg| arg == "-silent");
 
     if is_silent_mode {
         silent_run()?;
@@ -47,8 +48,13 @@ fn silent_run() -> AppResult<()> {
 
     rt.block_on(async {
         let startup_time = std::time::Instant::now();
-        let config = load_config().unwrap_or_default();
+        let (config, report) = load_config_with_report().unwrap_or_default();
         let auth_service = AuthService::new_with_startup_time(config.clone(), Some(startup_time));
+        if report.has_changes() {
+            let _ = auth_service
+                .get_message_center()
+                .log_event("INFO", &report.to_message());
+        }
         let _ = auth_service.silent_login(config).await?;
         Ok::<(), AppError>(())
     })?;
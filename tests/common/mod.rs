@@ -0,0 +1,309 @@
+//! 进程内的模拟Dr.COM认证系统
+//!
+//! 在本地随机端口上提供认证页面、`/drcom/login`、`/drcom/logout`接口，
+//! 以及用户自助服务系统的`/Self/login/`、`/Self/login/randomCode`、
+//! `/Self/login/verify`和`/Self/dashboard`页面，供集成测试离线运行
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 模拟自助服务系统登录页面中的checkcode
+const CHECKCODE: &str = "8421";
+
+/// 模拟认证系统的状态
+#[derive(Debug, Clone)]
+pub struct MockState {
+    /// 校园网账号（不含运营商后缀）
+    pub username: String,
+    /// 校园网密码
+    pub password: String,
+    /// 是否已登录校园网
+    pub logged_in: bool,
+    /// 剩余流量（MB）
+    pub left_flow: f64,
+    /// 是否已登录自助服务系统
+    pub self_service_logged_in: bool,
+    /// 在线设备的会话编号和IP地址
+    pub online_devices: Vec<(String, String)>,
+    /// 收到的请求路径，按顺序记录
+    pub requests: Vec<String>,
+    /// 最近一次登录请求的JSONP回调函数名
+    pub login_callback: String,
+}
+
+/// 模拟认证系统
+pub struct MockPortal {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockPortal {
+    /// 启动模拟认证系统
+    pub async fn start(username: &str, password: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(MockState {
+            username: username.to_string(),
+            password: password.to_string(),
+            logged_in: false,
+            left_flow: 20480.5,
+            self_service_logged_in: false,
+            online_devices: vec![
+                ("1001".to_string(), "10.33.12.34".to_string()),
+                ("1002".to_string(), "10.33.56.78".to_string()),
+            ],
+            requests: Vec::new(),
+            login_callback: String::new(),
+        }));
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, state).await;
+                });
+            }
+        });
+
+        Self { addr, state }
+    }
+
+    /// 认证页面地址，对应`network.login_ip`
+    pub fn login_ip(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// 自助服务系统地址，对应`network.self_service_url`
+    pub fn self_service_url(&self) -> String {
+        format!("http://{}/Self", self.addr)
+    }
+
+    /// 监听地址，可用作TCP探测目标
+    pub fn address(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// 修改模拟状态
+    pub fn update(&self, f: impl FnOnce(&mut MockState)) {
+        f(&mut self.state.lock().unwrap());
+    }
+
+    /// 当前模拟状态
+    pub fn state(&self) -> MockState {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+/// HTTP请求
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    form: HashMap<String, String>,
+}
+
+/// 读取一个HTTP请求并返回响应，每个连接只处理一个请求
+async fn handle_connection(
+    mut stream: TcpStream,
+    state: Arc<Mutex<MockState>>,
+) -> std::io::Result<()> {
+    let Some(request) = read_request(&mut stream).await? else {
+        return Ok(());
+    };
+
+    let (status, content_type, body) = route(&request, &state);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+/// 解析请求行、请求头和表单内容
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<Request>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default().to_string();
+
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let body = String::from_utf8_lossy(&buffer[header_end..]).to_string();
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), parse_pairs(query)),
+        None => (target, HashMap::new()),
+    };
+
+    Ok(Some(Request {
+        method,
+        path,
+        query,
+        form: parse_pairs(&body),
+    }))
+}
+
+/// 解析`a=1&b=2`形式的参数
+fn parse_pairs(text: &str) -> HashMap<String, String> {
+    text.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| {
+            let value = value.replace('+', " ");
+            (
+                urlencoding::decode(key).unwrap_or_default().to_string(),
+                urlencoding::decode(&value).unwrap_or_default().to_string(),
+            )
+        })
+        .collect()
+}
+
+/// 按路径分发请求
+fn route(
+    request: &Request,
+    state: &Arc<Mutex<MockState>>,
+) -> (&'static str, &'static str, Vec<u8>) {
+    let mut state = state.lock().unwrap();
+    state
+        .requests
+        .push(format!("{} {}", request.method, request.path));
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => {
+            let title = if state.logged_in {
+                "注销页"
+            } else {
+                "上网登录页"
+            };
+            html(format!(
+                "<html><head><title>{}</title></head></html>",
+                title
+            ))
+        }
+        ("GET", "/drcom/login") => {
+            let account = request.query.get("DDDDD").cloned().unwrap_or_default();
+            let username = account.split('@').next().unwrap_or_default();
+            let password = request.query.get("upass").cloned().unwrap_or_default();
+            state.login_callback = request.query.get("callback").cloned().unwrap_or_default();
+
+            let body = if username == state.username && password == state.password {
+                state.logged_in = true;
+                format!(
+                    r#"{}({{"result":1,"aolno":2315,"m46":0,"v46ip":"10.33.12.34","myv6ip":"","sms":0,"NID":"","olmac":"a1b2c3d4e5f6","gid":13,"uid":"{}"}});"#,
+                    state.login_callback, state.username
+                )
+            } else {
+                format!(
+                    r#"{}({{"result":0,"msg":"","ret_code":1,"msga":"ldap auth error"}});"#,
+                    state.login_callback
+                )
+            };
+            text(body)
+        }
+        ("GET", "/drcom/logout") => {
+            state.logged_in = false;
+            text(r#"dr1004({"result":1,"msg":"注销成功"});"#.to_string())
+        }
+        ("GET", "/Self/login/") => html(format!(
+            r#"<html><head><title>用户自助服务系统</title></head><body><form><input type="hidden" name="checkcode" value="{}"/><input name="account"/></form></body></html>"#,
+            CHECKCODE
+        )),
+        ("GET", "/Self/login/randomCode") => ("200 OK", "image/jpeg", vec![0xff, 0xd8, 0xff, 0xd9]),
+        ("POST", "/Self/login/verify") => {
+            let expected_password = format!("{:x}", md5::compute(state.password.as_bytes()));
+            state.self_service_logged_in = request.form.get("account") == Some(&state.username)
+                && request.form.get("password") == Some(&expected_password)
+                && request.form.get("checkcode").map(String::as_str) == Some(CHECKCODE)
+                && request.form.get("code").is_some_and(|code| code.len() == 4);
+            html(String::new())
+        }
+        ("GET", "/Self/dashboard") => {
+            if state.self_service_logged_in {
+                html(format!(
+                    r#"<html><body><dl><dt>{:.2} <small>M</small></dt><dd>剩余流量</dd></dl><script>var user = {{"leftFlow":{:.2},"leftMoney":12.5,"userGroupId":13,"userGroupName":"学生组-大一"}};</script></body></html>"#,
+                    state.left_flow, state.left_flow
+                ))
+            } else {
+                html(r#"<html><head><title>用户自助服务系统</title></head><body><input name="account"/></body></html>"#.to_string())
+            }
+        }
+        ("GET", "/Self/dashboard/getOnlineList") if state.self_service_logged_in => {
+            let rows: Vec<String> = state
+                .online_devices
+                .iter()
+                .map(|(session_id, ip)| {
+                    format!(
+                        r#"{{"sessionId":"{}","ip":"{}","mac":"A1B2C3D4E5F6","loginTime":"2025-10-01 08:00:00"}}"#,
+                        session_id, ip
+                    )
+                })
+                .collect();
+            json(format!("[{}]", rows.join(",")))
+        }
+        ("GET", "/Self/dashboard/tooffline") if state.self_service_logged_in => {
+            let session_id = request.query.get("sessionid").cloned().unwrap_or_default();
+            let count = state.online_devices.len();
+            state.online_devices.retain(|(id, _)| *id != session_id);
+            json(format!(
+                r#"{{"success":{}}}"#,
+                state.online_devices.len() < count
+            ))
+        }
+        ("GET", "/Self/dashboard/getOnlineList" | "/Self/dashboard/tooffline") => html(
+            r#"<html><head><title>用户自助服务系统</title></head><body><input name="account"/></body></html>"#.to_string(),
+        ),
+        _ => ("404 Not Found", "text/plain", Vec::new()),
+    }
+}
+
+fn html(body: String) -> (&'static str, &'static str, Vec<u8>) {
+    ("200 OK", "text/html; charset=utf-8", body.into_bytes())
+}
+
+fn json(body: String) -> (&'static str, &'static str, Vec<u8>) {
+    (
+        "200 OK",
+        "application/json; charset=utf-8",
+        body.into_bytes(),
+    )
+}
+
+fn text(body: String) -> (&'static str, &'static str, Vec<u8>) {
+    ("200 OK", "text/plain; charset=utf-8", body.into_bytes())
+}
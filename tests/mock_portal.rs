@@ -0,0 +1,503 @@
+//! 基于模拟认证系统的端到端测试，无需接入校园网
+
+mod common;
+
+use autologinguet_core::core::config::{AccountProfile, ConfigData, WatchConfig, save_config};
+use autologinguet_core::core::crypto::{
+    CiphertextFormat, ciphertext_format, encrypt_password_with_machine_key,
+    encrypt_password_with_passphrase,
+};
+use autologinguet_core::core::flow::{FlowError, FlowService};
+use autologinguet_core::core::flow_switch::{FlowSwitchConfig, FlowSwitchDecision};
+use autologinguet_core::core::message::{CampusNetworkStatus, WanStatus};
+use autologinguet_core::core::passphrase::set_session_passphrase;
+use autologinguet_core::core::paths::set_config_path;
+use autologinguet_core::core::probe::ProbeTarget;
+use autologinguet_core::core::secret_store::{MemorySecretStore, SecretBackend, SecretStore};
+use autologinguet_core::core::secret_string::SecretString;
+use autologinguet_core::core::watch::NetworkWatcher;
+use autologinguet_core::{AuthService, GuiConfigDto};
+use common::MockPortal;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+const USERNAME: &str = "2100000000";
+const PASSWORD: &str = "p@ss word";
+
+static WORKDIR: Once = Once::new();
+
+/// 把配置文件指定到临时目录并写入配置
+///
+/// `AuthService`会重新读取配置文件判断是否需要查询广域网和流量，
+/// 所有测试共用同一份配置，避免读写用户目录下的配置文件
+fn setup_workdir() {
+    WORKDIR.call_once(|| {
+        let dir = tempfile::tempdir().unwrap().keep();
+        set_config_path(dir.join("config.toml"));
+
+        let mut config = ConfigData::default();
+        config.account.username = USERNAME.to_string();
+        config.account.encrypted_password = encrypt_password_with_machine_key(PASSWORD).unwrap();
+        config.logging.enable_logging = false;
+        save_config(&config).unwrap();
+    });
+}
+
+/// 指向模拟认证系统的配置
+fn mock_config(portal: &MockPortal, password: &str) -> ConfigData {
+    setup_workdir();
+
+    let mut config = ConfigData::default();
+    config.account.username = USERNAME.to_string();
+    config.account.encrypted_password = encrypt_password_with_machine_key(password).unwrap();
+    config.logging.enable_logging = false;
+    config.network.login_ip = portal.login_ip();
+    config.network.self_service_url = portal.self_service_url();
+    config.network.probes.timeout_secs = 2;
+    config.network.probes.targets = vec![ProbeTarget::Tcp {
+        address: portal.address(),
+    }];
+    config
+}
+
+#[tokio::test]
+async fn test_silent_login_against_mock_portal() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let config = mock_config(&portal, PASSWORD);
+
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(result.success, "{}", result.message);
+    assert!(
+        result.message.contains("登录校园网成功"),
+        "{}",
+        result.message
+    );
+    assert!(portal.state().logged_in);
+    assert_eq!(portal.state().login_callback, "dr1003");
+}
+
+#[tokio::test]
+async fn test_silent_login_uses_configured_callback() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let mut config = mock_config(&portal, PASSWORD);
+    config.network.portal.callback = "jsonp1700000000".to_string();
+
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(result.success, "{}", result.message);
+    assert_eq!(portal.state().login_callback, "jsonp1700000000");
+}
+
+#[tokio::test]
+async fn test_silent_login_reports_wrong_password() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let config = mock_config(&portal, "wrong");
+
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(!result.success);
+    assert!(
+        result.message.contains("账号或密码错误"),
+        "{}",
+        result.message
+    );
+    assert!(!portal.state().logged_in);
+}
+
+#[tokio::test]
+async fn test_silent_login_falls_back_to_next_profile() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let mut config = mock_config(&portal, "wrong");
+    config.profiles.fallback = vec!["backup".to_string()];
+    config.profiles.accounts = vec![AccountProfile {
+        name: "backup".to_string(),
+        username: USERNAME.to_string(),
+        encrypted_password: encrypt_password_with_machine_key(PASSWORD).unwrap(),
+        isp: String::new(),
+    }];
+
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(result.success, "{}", result.message);
+    assert!(portal.state().logged_in);
+    assert_eq!(
+        portal
+            .state()
+            .requests
+            .iter()
+            .filter(|request| request.ends_with("/drcom/login"))
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_flow_switch_between_campus_and_isp_profiles() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    portal.update(|state| {
+        state.logged_in = true;
+        state.left_flow = 100.0;
+    });
+
+    let mut config = mock_config(&portal, PASSWORD);
+    let encrypted_password = config.account.encrypted_password.clone();
+    config.profiles.default = "campus".to_string();
+    config.profiles.accounts = ["campus", "isp"]
+        .iter()
+        .map(|name| AccountProfile {
+            name: name.to_string(),
+            username: USERNAME.to_string(),
+            encrypted_password: encrypted_password.clone(),
+            isp: if *name == "isp" { "cmcc" } else { "" }.to_string(),
+        })
+        .collect();
+    config.flow_switch = FlowSwitchConfig {
+        enabled: true,
+        threshold_mb: 512.0,
+        campus_profile: "campus".to_string(),
+        isp_profile: "isp".to_string(),
+        ..Default::default()
+    };
+
+    let auth_service = AuthService::new(config.clone());
+    let decision = auth_service.apply_flow_switch(&mut config).await.unwrap();
+    assert_eq!(decision, FlowSwitchDecision::SwitchToIsp);
+    assert_eq!(config.profiles.default, "isp");
+    assert_eq!(config.account.isp, "cmcc");
+    assert!(portal.state().logged_in);
+    assert!(
+        portal
+            .state()
+            .requests
+            .iter()
+            .any(|request| request.ends_with("/drcom/logout"))
+    );
+
+    // 计费周期重置后切换回校园网账号
+    portal.update(|state| state.left_flow = 20480.0);
+    let decision = auth_service.apply_flow_switch(&mut config).await.unwrap();
+    assert_eq!(decision, FlowSwitchDecision::SwitchToCampus);
+    assert_eq!(config.profiles.default, "campus");
+    assert!(config.account.isp.is_empty());
+
+    let decision = auth_service.apply_flow_switch(&mut config).await.unwrap();
+    assert_eq!(decision, FlowSwitchDecision::Stay);
+}
+
+#[tokio::test]
+async fn test_flow_switch_restores_previous_profile_when_target_login_fails() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    portal.update(|state| {
+        state.logged_in = true;
+        state.left_flow = 100.0;
+    });
+
+    let mut config = mock_config(&portal, PASSWORD);
+    config.profiles.default = "campus".to_string();
+    config.profiles.accounts = vec![
+        AccountProfile {
+            name: "campus".to_string(),
+            username: USERNAME.to_string(),
+            encrypted_password: config.account.encrypted_password.clone(),
+            isp: String::new(),
+        },
+        AccountProfile {
+            name: "isp".to_string(),
+            username: USERNAME.to_string(),
+            encrypted_password: encrypt_password_with_machine_key("wrong").unwrap(),
+            isp: "cmcc".to_string(),
+        },
+    ];
+    config.flow_switch = FlowSwitchConfig {
+        enabled: true,
+        threshold_mb: 512.0,
+        campus_profile: "campus".to_string(),
+        isp_profile: "isp".to_string(),
+        ..Default::default()
+    };
+
+    let auth_service = AuthService::new(config.clone());
+    assert!(auth_service.apply_flow_switch(&mut config).await.is_err());
+    assert_eq!(config.profiles.default, "campus");
+    assert!(config.account.isp.is_empty());
+    assert!(portal.state().logged_in);
+    assert_eq!(
+        portal
+            .state()
+            .requests
+            .iter()
+            .filter(|request| request.ends_with("/drcom/login"))
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_watcher_relogins_and_backs_off_after_logout() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    portal.update(|state| state.logged_in = true);
+
+    let mut config = mock_config(&portal, PASSWORD);
+    config.watch = WatchConfig {
+        interval_secs: 30,
+        max_backoff_secs: 100,
+        check_wan: false,
+    };
+
+    let auth_service = AuthService::new(config.clone());
+    let mut watcher = NetworkWatcher::new(auth_service, config);
+    assert_eq!(watcher.tick().await, Duration::from_secs(30));
+
+    // 会话失效且密码已被修改，重新登录失败后按指数退避
+    portal.update(|state| {
+        state.logged_in = false;
+        state.password = "changed".to_string();
+    });
+    assert_eq!(watcher.tick().await, Duration::from_secs(60));
+    assert_eq!(watcher.tick().await, Duration::from_secs(100));
+    assert!(!portal.state().logged_in);
+    assert_eq!(
+        portal
+            .state()
+            .requests
+            .iter()
+            .filter(|request| request.ends_with("/drcom/login"))
+            .count(),
+        2
+    );
+
+    portal.update(|state| state.password = PASSWORD.to_string());
+    assert_eq!(watcher.tick().await, Duration::from_secs(30));
+    assert!(portal.state().logged_in);
+}
+
+#[tokio::test]
+async fn test_watcher_relogins_with_fallback_profile() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let mut config = mock_config(&portal, "wrong");
+    config.watch.check_wan = false;
+    config.profiles.fallback = vec!["backup".to_string()];
+    config.profiles.accounts = vec![AccountProfile {
+        name: "backup".to_string(),
+        username: USERNAME.to_string(),
+        encrypted_password: encrypt_password_with_machine_key(PASSWORD).unwrap(),
+        isp: String::new(),
+    }];
+
+    let auth_service = AuthService::new(config.clone());
+    let mut watcher = NetworkWatcher::new(auth_service, config.clone());
+    assert_eq!(
+        watcher.tick().await,
+        Duration::from_secs(config.watch.interval_secs)
+    );
+    assert!(portal.state().logged_in);
+}
+
+#[tokio::test]
+async fn test_silent_login_with_passphrase_wrapped_password() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let mut config = mock_config(&portal, PASSWORD);
+    let count = config
+        .rewrap_passwords(|password| encrypt_password_with_passphrase(password, "master"))
+        .unwrap();
+    assert_eq!(count, 1);
+    assert!(config.uses_passphrase());
+
+    // 未提供主密码时无法解密
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config.clone()).await.unwrap();
+    assert!(!result.success);
+    assert!(result.message.contains("主密码"), "{}", result.message);
+
+    // GUI保存新密码时保持主密码加密，未提供主密码时拒绝保存
+    let mut gui_config = GuiConfigDto::from(config.clone());
+    gui_config.password = SecretString::from("new password");
+    assert!(gui_config.to_config().is_err());
+
+    set_session_passphrase(SecretString::from("master"));
+    let result = auth_service.silent_login(config.clone()).await.unwrap();
+    assert!(result.success, "{}", result.message);
+
+    let saved = gui_config.to_config().unwrap();
+    assert_eq!(
+        ciphertext_format(&saved.account.encrypted_password),
+        CiphertextFormat::PassphraseV2
+    );
+
+    config
+        .rewrap_passwords(encrypt_password_with_machine_key)
+        .unwrap();
+    assert_eq!(
+        ciphertext_format(&config.account.encrypted_password),
+        CiphertextFormat::AeadV2
+    );
+}
+
+#[tokio::test]
+async fn test_silent_login_reads_password_from_secret_store() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let mut config = mock_config(&portal, PASSWORD);
+    config.secret.backend = SecretBackend::Keyring;
+
+    let secret_store = Arc::new(MemorySecretStore::new());
+    secret_store
+        .set_password(&mut config.account, PASSWORD)
+        .unwrap();
+    assert!(config.account.encrypted_password.is_empty());
+
+    let mut auth_service = AuthService::new(config.clone());
+    auth_service.set_secret_store(secret_store);
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(result.success, "{}", result.message);
+    assert!(portal.state().logged_in);
+}
+
+#[tokio::test]
+async fn test_silent_login_skips_login_when_already_logged_in() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    portal.update(|state| state.logged_in = true);
+    let config = mock_config(&portal, PASSWORD);
+
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(result.success);
+    assert!(
+        result.message.contains("已登录校园网"),
+        "{}",
+        result.message
+    );
+    assert!(
+        !portal
+            .state()
+            .requests
+            .iter()
+            .any(|request| request.ends_with("/drcom/login"))
+    );
+}
+
+#[tokio::test]
+async fn test_silent_login_renders_named_message_template() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let mut config = mock_config(&portal, PASSWORD);
+    config.message.gui_text =
+        "{username} %1{?flow}，剩余{flow:GB.1}GB，余额{balance:.1}元{/flow}{?ip}（{ip}）{/ip}"
+            .to_string();
+
+    let auth_service = AuthService::new(config.clone());
+    let result = auth_service.silent_login(config).await.unwrap();
+
+    assert!(result.success, "{}", result.message);
+    assert_eq!(
+        result.message,
+        format!(
+            "{} 登录校园网成功！，剩余20.0GB，余额12.5元（127.0.0.1）",
+            USERNAME
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_check_network_status_and_logout() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    portal.update(|state| state.logged_in = true);
+    let config = mock_config(&portal, PASSWORD);
+
+    let auth_service = AuthService::new(config);
+    let (campus_status, wan_status) = auth_service.check_network_status(false).await.unwrap();
+    assert_eq!(campus_status, CampusNetworkStatus::AlreadyLoggedIn);
+    assert_eq!(wan_status, WanStatus::Connected);
+
+    let logout_result = auth_service.logout().await.unwrap();
+    assert!(logout_result.success, "{}", logout_result.message);
+
+    let (campus_status, _) = auth_service.check_network_status(false).await.unwrap();
+    assert_eq!(campus_status, CampusNetworkStatus::NotLoggedIn);
+}
+
+#[tokio::test]
+async fn test_get_user_flow_info_from_mock_self_service() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let flow_service = FlowService::new().with_base_url(&portal.self_service_url());
+    let password = SecretString::from(PASSWORD);
+
+    let flow_info = flow_service
+        .get_user_flow_info(USERNAME, &password)
+        .await
+        .unwrap();
+    assert_eq!(flow_info.left_flow, 20480.5);
+
+    let account_info = flow_service
+        .get_account_info(USERNAME, &password)
+        .await
+        .unwrap();
+    assert_eq!(account_info.balance, Some(12.5));
+    assert_eq!(account_info.user_group_id, Some(13));
+    assert_eq!(account_info.format_package(), "套餐学生组-大一");
+
+    let flow_service = FlowService::new().with_base_url(&portal.self_service_url());
+    let result = flow_service
+        .get_user_flow_info(USERNAME, &SecretString::from("wrong"))
+        .await;
+    assert!(matches!(result, Err(FlowError::LoginFailed(_))));
+}
+
+#[tokio::test]
+async fn test_list_and_kick_online_devices() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let auth_service = AuthService::new(mock_config(&portal, PASSWORD));
+
+    let devices = auth_service.list_online_devices().await.unwrap();
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[1].ip, "10.33.56.78");
+
+    auth_service
+        .kick_device(&devices[1].session_id)
+        .await
+        .unwrap();
+    assert_eq!(portal.state().online_devices.len(), 1);
+
+    let result = auth_service.kick_device("9999").await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_self_service_session_is_reused_until_expired() {
+    let portal = MockPortal::start(USERNAME, PASSWORD).await;
+    let flow_service = FlowService::new().with_base_url(&portal.self_service_url());
+    let login_count = |portal: &MockPortal| {
+        portal
+            .state()
+            .requests
+            .iter()
+            .filter(|request| *request == "POST /Self/login/verify")
+            .count()
+    };
+    let password = SecretString::from(PASSWORD);
+
+    let cloned_service = flow_service.clone();
+    let (first, second) = tokio::join!(
+        flow_service.get_user_flow_info(USERNAME, &password),
+        cloned_service.get_user_flow_info(USERNAME, &password)
+    );
+    assert_eq!(first.unwrap().left_flow, second.unwrap().left_flow);
+    flow_service
+        .list_online_devices(USERNAME, &password)
+        .await
+        .unwrap();
+    assert_eq!(login_count(&portal), 1);
+
+    // 服务端会话失效后重新登录
+    portal.update(|state| state.self_service_logged_in = false);
+    flow_service
+        .list_online_devices(USERNAME, &password)
+        .await
+        .unwrap();
+    assert_eq!(login_count(&portal), 2);
+}